@@ -0,0 +1,1968 @@
+mod input;
+mod render;
+
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{self, KeyCode};
+use rand::rngs::StdRng;
+use rand::{SeedableRng, rng};
+use ratatui::DefaultTerminal;
+use ratatui::layout::Rect;
+
+use crate::config::{ConfirmQuit, Escape, Movement, Theme, WinScreen};
+use crate::date::Date;
+use crate::grid::{Direction, Goal, Grid, random_move, rotate_cell};
+use crate::keys::KeyBindings;
+use crate::layout::LayoutConfig;
+use crate::notation::MoveList;
+use crate::stats::{
+  BestMoves, BestRuns, BestTournaments, MoveMetric, RecentSolves, Run, TournamentResult, data_dir,
+  record_key,
+};
+use crate::{
+  Align, CAMPAIGN_START, Difficulty, Mode, Options, new_grid, seeded_grid, solver, title,
+};
+
+/// How long to wait for input before redrawing anyway, so timed effects can update.
+const TICK: Duration = Duration::from_millis(50);
+
+/// How long to wait between moves in attract mode.
+const ATTRACT_PACE: Duration = Duration::from_millis(400);
+
+/// How long to wait between moves in a showcase.
+const SHOWCASE_PACE: Duration = Duration::from_millis(250);
+
+/// The speeds a showcase can play at, as multiples of its usual pace, from slowest to
+/// fastest.
+const SHOWCASE_SPEEDS: [f64; 7] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0, 16.0];
+
+/// Where in SHOWCASE_SPEEDS a showcase starts: its usual pace.
+const SHOWCASE_NORMAL_SPEED: usize = 2;
+
+/// How many random moves a showcase makes before solving the board again.
+const SHOWCASE_SCRAMBLE: usize = 20;
+
+/// How long a showcase rests on the solved board before scrambling it again, and how
+/// long the demo shows its starting board before solving it.
+const SHOWCASE_REST: Duration = Duration::from_millis(1500);
+
+/// The seed for the demo's scrambles, so it looks the same every time.
+const DEMO_SEED: u64 = 15;
+
+/// How long coach mode's note stays up after a move that made things worse.
+const COACH_NOTE_DURATION: Duration = Duration::from_millis(1500);
+
+/// How many of the blank's last cells its trail marks.
+const TRAIL_LENGTH: usize = 4;
+
+/// How long each cell of the blank's trail takes to fade away.
+const TRAIL_FADE: Duration = Duration::from_millis(1200);
+
+/// How often to look for more moves in the file being spectated, and for the file
+/// itself before it exists.
+const SPECTATE_POLL: Duration = Duration::from_millis(100);
+
+/// How long a whole row or column takes to slide into place.
+const SLIDE_ANIMATION: Duration = Duration::from_millis(150);
+
+/// How often to redraw while something's moving.
+const FRAME: Duration = Duration::from_millis(16);
+
+/// How long the edge of the board flashes after an illegal move.
+const FLASH_DURATION: Duration = Duration::from_millis(150);
+
+/// One key press's worth of sliding: the direction, and how many tiles slid.
+#[derive(Clone, Copy)]
+struct Move {
+  direction: Direction,
+  slides: u32,
+}
+
+/// A puzzle solved this session.
+struct Solved {
+  /// The starting position's code, which is enough to play it again.
+  code: String,
+  moves: u32,
+  time: Duration,
+}
+
+/// The state of the seed browser, where the player looks through seeded puzzles.
+struct Browser {
+  seed: u64,
+  /// Each seed's puzzle, made as it's shown. How long it takes to solve is worked out
+  /// by the worker.
+  previews: HashMap<u64, Grid>,
+}
+
+/// Which puzzles make up a tournament.
+#[derive(Clone, Copy)]
+enum TournamentKind {
+  /// Seeded puzzles of one size. Puzzle i uses the seed after the first by i.
+  Seeds { seed: u64, size: usize },
+  /// A campaign: freshly shuffled puzzles, one of each size, from the smallest board up.
+  Sizes,
+  /// The daily puzzle: a single one, seeded by its date.
+  Daily { date: Date, size: usize },
+}
+
+/// The state of a tournament, where the player solves a run of puzzles one after another.
+struct Tournament {
+  kind: TournamentKind,
+  /// How many puzzles there are in all.
+  count: usize,
+  /// Which puzzle the player is on, counting from 0.
+  current: usize,
+  /// The moves used on the puzzles solved so far.
+  moves: u32,
+  /// When the first puzzle started, moved later by any time spent paused. The clock
+  /// keeps running until the last is solved.
+  started: Instant,
+  /// The combined result, once every puzzle has been solved.
+  result: Option<TournamentResult>,
+  /// The best results from previous runs.
+  best: BestTournaments,
+}
+
+impl Tournament {
+  /// What to call this kind of tournament in messages.
+  fn title(&self) -> &'static str {
+    match self.kind {
+      TournamentKind::Seeds { .. } => "tournament",
+      TournamentKind::Sizes => "campaign",
+      TournamentKind::Daily { .. } => "daily puzzle",
+    }
+  }
+
+  /// The size of board for the given puzzle, counting from 0, and its seed if it has one.
+  fn puzzle(&self, index: usize) -> (usize, Option<u64>) {
+    match self.kind {
+      TournamentKind::Seeds { seed, size } => (size, Some(seed.wrapping_add(index as u64))),
+      TournamentKind::Sizes => (CAMPAIGN_START + index, None),
+      TournamentKind::Daily { date, size } => (size, Some(date.seed())),
+    }
+  }
+
+  /// A name for this set of puzzles, to keep its best result under. Different sizes,
+  /// goals and difficulties give different puzzles for the same seeds.
+  fn name(&self, goal: &Goal, difficulty: Difficulty) -> String {
+    Tournament::name_of(self.kind, self.count, goal, difficulty)
+  }
+
+  /// The name for any tournament, for looking up ones other than this.
+  fn name_of(kind: TournamentKind, count: usize, goal: &Goal, difficulty: Difficulty) -> String {
+    let mut name = match kind {
+      TournamentKind::Seeds { seed, size } => {
+        format!("{size}x{size}-{}-{seed}-{count}", difficulty.id())
+      }
+      TournamentKind::Sizes => {
+        let last = CAMPAIGN_START + count - 1;
+        format!("campaign-{}-to-{last}x{last}", difficulty.id())
+      }
+      TournamentKind::Daily { date, size } => {
+        format!("{size}x{size}-{}-daily-{date}", difficulty.id())
+      }
+    };
+    if *goal != Goal::Standard {
+      name = format!("{name}-{}", goal.id());
+    }
+    name
+  }
+
+  /// For a finished daily puzzle, how its time compares with the best on the other
+  /// days of the same month, like "3rd fastest of 7 dailies in 2024-01".
+  fn daily_rank(&self, goal: &Goal, difficulty: Difficulty) -> Option<String> {
+    let TournamentKind::Daily { date, size } = self.kind else {
+      return None;
+    };
+    let result = self.result?;
+    let others: Vec<Duration> = date
+      .month_days()
+      .filter(|&day| day != date)
+      .filter_map(|day| {
+        let kind = TournamentKind::Daily { date: day, size };
+        self
+          .best
+          .get(&Tournament::name_of(kind, 1, goal, difficulty))
+      })
+      .map(|best| best.time)
+      .collect();
+    let month = date.month_name();
+    if others.is_empty() {
+      return Some(format!("The only daily you've solved in {month}"));
+    }
+    let rank = 1 + others.iter().filter(|&&time| time < result.time).count();
+    Some(format!(
+      "{} fastest of {} dailies in {month}",
+      ordinal(rank),
+      others.len() + 1
+    ))
+  }
+}
+
+/// A number as a position in a list, like "1st" or "12th".
+fn ordinal(n: usize) -> String {
+  let suffix = match (n % 10, n % 100) {
+    (_, 11..=13) => "th",
+    (1, _) => "st",
+    (2, _) => "nd",
+    (3, _) => "rd",
+    _ => "th",
+  };
+  format!("{n}{suffix}")
+}
+
+/// The state of attract mode, where the board moves by itself.
+struct Attract {
+  last: Option<Direction>,
+  next_move: Instant,
+  /// The player's board, when attract mode started because they were away. It comes
+  /// back untouched on the next key press.
+  saved: Option<Grid>,
+}
+
+/// A row or column of tiles sliding together, from one key press.
+struct SlideAnimation {
+  direction: Direction,
+  /// Where the moving tiles end up.
+  cells: Vec<(usize, usize)>,
+  started: Instant,
+}
+
+impl SlideAnimation {
+  /// How far along the slide is, from 0 at the start to 1 when it's done.
+  fn progress(&self) -> f64 {
+    (self.started.elapsed().as_secs_f64() / SLIDE_ANIMATION.as_secs_f64()).min(1.0)
+  }
+}
+
+/// Another program's moves, read from a file as they're written.
+struct Spectate {
+  path: PathBuf,
+  letters: Receiver<char>,
+  /// When the next move can be made, if moves are capped.
+  next_move: Instant,
+}
+
+impl Spectate {
+  /// Start watching the file. It's read on a thread of its own, since reading from a
+  /// named pipe waits until there's something to read.
+  fn start(path: PathBuf) -> Self {
+    let (sender, letters) = mpsc::channel();
+    let watched = path.clone();
+    thread::spawn(move || {
+      let mut file = loop {
+        match File::open(&watched) {
+          Ok(file) => break file,
+          Err(_) => thread::sleep(SPECTATE_POLL),
+        }
+      };
+      let mut buf = [0; 256];
+      loop {
+        match file.read(&mut buf) {
+          Ok(0) => thread::sleep(SPECTATE_POLL),
+          Ok(n) => {
+            for letter in String::from_utf8_lossy(&buf[..n]).chars() {
+              if sender.send(letter).is_err() {
+                // Nobody's watching any more.
+                return;
+              }
+            }
+          }
+          Err(_) => return,
+        }
+      }
+    });
+    Spectate {
+      path,
+      letters,
+      next_move: Instant::now(),
+    }
+  }
+}
+
+/// Solves boards on a thread of its own, so a hard one doesn't hold up the game.
+struct Worker {
+  boards: Sender<Grid>,
+  solutions: Receiver<(Grid, Vec<Direction>)>,
+  /// The last board sent to be solved, until its solution comes back.
+  solving: Option<Grid>,
+}
+
+impl Worker {
+  fn start() -> Self {
+    let (boards, requests) = mpsc::channel::<Grid>();
+    let (sender, solutions) = mpsc::channel();
+    thread::spawn(move || {
+      while let Ok(mut grid) = requests.recv() {
+        // Only the newest board is worth solving. The player's moved on from the rest.
+        while let Ok(newer) = requests.try_recv() {
+          grid = newer;
+        }
+        let path = solver::solve(&grid).unwrap_or_default();
+        if sender.send((grid, path)).is_err() {
+          // The game's over.
+          return;
+        }
+      }
+    });
+    Worker {
+      boards,
+      solutions,
+      solving: None,
+    }
+  }
+
+  /// Start solving the board, unless it's the one already being solved.
+  fn solve(&mut self, grid: &Grid) {
+    if self.solving.as_ref() != Some(grid) && self.boards.send(grid.clone()).is_ok() {
+      self.solving = Some(grid.clone());
+    }
+  }
+}
+
+/// The state of a showcase, where the board scrambles and solves itself over and over.
+struct Showcase {
+  /// The moves still to make in this round, scramble first and then the solution.
+  moves: VecDeque<Direction>,
+  next_move: Instant,
+  /// Where the scrambles come from. Seeded for the demo, so it's the same every time.
+  rng: StdRng,
+  /// For the demo, the board to play once it's stopped.
+  demo: Option<Grid>,
+  /// How fast it's playing, as an index into SHOWCASE_SPEEDS.
+  speed: usize,
+  /// Whether it's stopped on the current move, for a closer look.
+  paused: bool,
+  /// The shortest time between moves, however fast it's set to play.
+  cap: Option<Duration>,
+}
+
+impl Showcase {
+  fn new(rng: StdRng, demo: Option<Grid>, next_move: Instant) -> Showcase {
+    Showcase {
+      moves: VecDeque::new(),
+      next_move,
+      rng,
+      demo,
+      speed: SHOWCASE_NORMAL_SPEED,
+      paused: false,
+      cap: None,
+    }
+  }
+
+  /// How long to wait for the next move, at the current speed.
+  fn pace(&self) -> Duration {
+    let pace = if self.moves.is_empty() {
+      SHOWCASE_REST
+    } else {
+      SHOWCASE_PACE
+    };
+    pace
+      .div_f64(SHOWCASE_SPEEDS[self.speed])
+      .max(self.cap.unwrap_or_default())
+  }
+
+  /// Whether the cap is holding it back from the speed it's set to.
+  fn capped(&self) -> bool {
+    self
+      .cap
+      .is_some_and(|cap| cap > SHOWCASE_PACE.div_f64(SHOWCASE_SPEEDS[self.speed]))
+  }
+
+  /// Handle a key that changes how the showcase plays: + (or =) and - for faster and
+  /// slower, and space to pause. Returns false for any other key.
+  fn control(&mut self, code: KeyCode) -> bool {
+    match code {
+      KeyCode::Char('+' | '=') => self.speed = (self.speed + 1).min(SHOWCASE_SPEEDS.len() - 1),
+      KeyCode::Char('-') => self.speed = self.speed.saturating_sub(1),
+      KeyCode::Char(' ') => {
+        self.paused = !self.paused;
+        // Pick up again with a full wait, not a move straight away.
+        self.next_move = Instant::now() + self.pace();
+      }
+      _ => return false,
+    }
+    true
+  }
+
+  /// The status line while it's playing.
+  fn status(&self) -> String {
+    let name = if self.demo.is_some() {
+      "Demo"
+    } else {
+      "Showcase"
+    };
+    let speed = if self.paused {
+      "paused".to_string()
+    } else if self.capped() {
+      format!("{}x, capped", SHOWCASE_SPEEDS[self.speed])
+    } else {
+      format!("{}x", SHOWCASE_SPEEDS[self.speed])
+    };
+    let stop = if self.demo.is_some() { "play" } else { "stop" };
+    format!("{name} ({speed}): +/- speed, space pauses, any other key to {stop}")
+  }
+}
+
+pub struct App {
+  grid: Grid,
+  /// The grid as it was shuffled, before any moves were made.
+  start: Grid,
+  keys: KeyBindings,
+  /// Colors from the config file.
+  pub theme: Theme,
+  /// The themes to switch to next, in order. The current one goes to the back.
+  pub themes: VecDeque<Theme>,
+  /// Whether to use colors at all.
+  color: bool,
+  pub difficulty: Difficulty,
+  /// The fewest moves a hard puzzle may take to solve, from the config file.
+  min_hard_moves: Option<usize>,
+  /// How many moves the starting position takes to solve, when it was shuffled to be at
+  /// least `min_hard_moves`.
+  start_moves: Option<usize>,
+  /// How many tiles the player has slid on this puzzle.
+  moves: u32,
+  /// How many key presses have moved tiles on this puzzle. Less than `moves` when whole
+  /// rows are slid at once.
+  key_presses: u32,
+  /// How many tiles the player has slid each way on this puzzle. Undone moves still
+  /// count, as they do in `moves`.
+  direction_moves: HashMap<Direction, u32>,
+  /// Which of the two counts a solve is scored by.
+  move_metric: MoveMetric,
+  /// When the player started on this puzzle, moved later by any time spent paused.
+  started: Instant,
+  /// When the clock was stopped, while the terminal is in the background or after
+  /// Escape.
+  paused: Option<Instant>,
+  /// Whether the pause going on is from Escape, rather than the terminal being in the
+  /// background.
+  escape_pause: bool,
+  /// How long the clock has been stopped on this puzzle, not counting a pause that's
+  /// still going.
+  paused_time: Duration,
+  /// How long this puzzle took to solve, once it's been solved. It's only added to
+  /// `solved` the first time.
+  solve_time: Option<Duration>,
+  /// The puzzles solved this session, oldest first.
+  solved: Vec<Solved>,
+  /// The selected entry while the list of solved puzzles is showing.
+  history_view: Option<usize>,
+  /// Set while the seed browser is showing.
+  browser: Option<Browser>,
+  /// The player's fewest-moves records from previous runs.
+  best: BestMoves,
+  /// The moves of the player's best solves, to race with --ghost.
+  runs: BestRuns,
+  /// Every tile slid on this puzzle so far, with when, to keep if it's a new best.
+  run: Run,
+  /// Whether to show the best run from this position alongside the game.
+  ghost: bool,
+  /// Someone else's records from --reference-scores, to compare against.
+  pub reference: Option<BestMoves>,
+  /// How the latest solves went, including previous runs, for showing a trend and
+  /// suggesting a difficulty.
+  recent: RecentSolves,
+  /// Whether to suggest another difficulty after a win.
+  advise: bool,
+  /// The difficulty suggested after this puzzle was solved.
+  advice: Option<Difficulty>,
+  /// Every move made since the last shuffle, so they can be undone.
+  history: Vec<Move>,
+  /// How many undos are allowed per puzzle, if they're limited.
+  undo_limit: Option<u32>,
+  /// How many undos have been used on this puzzle. A rewind counts as one.
+  undos_used: u32,
+  /// The number of moves in the history when the checkpoint was set, if there is one.
+  checkpoint: Option<usize>,
+  /// Whether the player has gone back to a checkpoint on this puzzle, which rules out a
+  /// clean solve.
+  checkpoint_used: bool,
+  /// A short note for the player, shown until the next key press.
+  pub message: Option<String>,
+  /// Whether to flash the edge of the board when a move can't be made.
+  flash_illegal_moves: bool,
+  /// The edge of the board that's flashing, named by the direction of the move that
+  /// couldn't be made, and when the flash ends.
+  flash: Option<(Direction, Instant)>,
+  /// Whether to point out moves that take the board further from solved.
+  coach: bool,
+  /// Which cells, row by row, hold a tile the player has locked in place.
+  locked: Vec<bool>,
+  /// Whether to lock tiles as soon as they're in place.
+  auto_lock: bool,
+  /// Whether the finish row key is allowed.
+  row_assist: bool,
+  /// Whether a row was finished for the player on this puzzle, which rules out a clean
+  /// solve.
+  assisted: bool,
+  /// Whether the player is studying the solution a move at a time.
+  studying: bool,
+  /// Whether the solver's moves were stepped through on this puzzle. It's a study aid,
+  /// not a solve, so it isn't recorded.
+  studied: bool,
+  /// When coach mode's note about the last move goes away, if it's showing.
+  coach_note: Option<Instant>,
+  /// Whether to show the fewest moves left to solve the board, with --optimal.
+  track_optimal: bool,
+  /// Solutions already found, so going back to an earlier position doesn't solve it again.
+  solutions: HashMap<Grid, Vec<Direction>>,
+  /// Where boards go to be solved without holding up the game.
+  worker: Worker,
+  /// Whether the player asked for a hint that's still being worked out.
+  hint_wanted: bool,
+  /// Set while the board is moving by itself in attract mode.
+  attract: Option<Attract>,
+  /// How long to wait without input before starting attract mode, if it should start
+  /// by itself.
+  idle_attract: Option<Duration>,
+  /// The shortest time between moves the board makes by itself, if they're capped.
+  move_cap: Option<Duration>,
+  /// When the player last pressed a key.
+  last_input: Instant,
+  /// When the player last moved a tile, or when the puzzle started if they haven't yet.
+  last_move: Instant,
+  /// Set while playing another program's moves from a file.
+  spectate: Option<Spectate>,
+  /// Set while a showcase is running.
+  showcase: Option<Showcase>,
+  /// Set while playing a tournament, and after finishing one.
+  tournament: Option<Tournament>,
+  /// Whether to label each tile with the position it belongs in.
+  show_goals: bool,
+  /// Whether to show how many pairs of tiles are out of order.
+  show_inversions: bool,
+  /// Whether the line of key instructions is shown under the title.
+  pub show_instructions: bool,
+  /// Whether to show how long it's been since the last move.
+  pub show_idle: bool,
+  /// How many quarter turns clockwise the board is shown turned. Only the view turns:
+  /// the grid, its goal and the moves made are all the right way up.
+  rotation: u8,
+  /// Blank cells to leave around the whole game, when there's room.
+  pub padding: u16,
+  /// Whether tile numbers are hidden except under the mouse.
+  reveal_on_hover: bool,
+  /// The cell under the mouse, if it's over the board.
+  hover: Option<(usize, usize)>,
+  /// The whole terminal, as of the last time it was drawn, to find the board in.
+  screen: Rect,
+  /// The first column and row of tiles on screen, for boards too big to fit. These are
+  /// as the board's shown, after any rotation.
+  scroll: (usize, usize),
+  /// Where the blank was shown the last time the view scrolled to keep it in sight.
+  /// Until it moves again, the player can pan away from it.
+  followed: Option<(usize, usize)>,
+  /// When pressing the quit key once isn't enough.
+  pub confirm_quit: ConfirmQuit,
+  /// What Escape does on the board.
+  pub escape: Escape,
+  /// Whether a win brings up the summary straight away.
+  pub win_screen: WinScreen,
+  /// Whether the movement keys move tiles or the blank.
+  pub movement: Movement,
+  /// Whether the summary of a solve is showing over the board.
+  summary: bool,
+  /// Whether the position's code is showing over the board, to be shared.
+  sharing: bool,
+  /// Set after the quit key was pressed once, when it needs pressing again.
+  quitting: bool,
+  /// Whether whole rows and columns slide smoothly.
+  animate: bool,
+  /// Set while a row or column is sliding into place.
+  slide_animation: Option<SlideAnimation>,
+  /// Set while the peek key is held, showing the goal labels until it's let go or until
+  /// this time, whichever comes first.
+  peek: Option<Instant>,
+  /// Whether to mark the rows and columns that are finished.
+  show_lines: bool,
+  /// Whether to show faintly which number belongs in each cell.
+  show_ghosts: bool,
+  /// The cells the blank has recently left, newest first, with when it left them. Only
+  /// kept while the trail is turned on.
+  trail: Option<VecDeque<((usize, usize), Instant)>>,
+  /// The cell picked for the blank to walk to, while the player is choosing one.
+  blank_target: Option<(usize, usize)>,
+  layout: LayoutConfig,
+  /// Where the board goes across the terminal.
+  align: Align,
+  /// The terminal title last set, or None if we're leaving the title alone.
+  title: Option<String>,
+  exit: bool,
+}
+
+impl App {
+  pub fn new(
+    options: Options,
+    grid: Grid,
+    keys: KeyBindings,
+    theme: Theme,
+    move_metric: MoveMetric,
+    min_hard_moves: Option<usize>,
+    idle_attract: Option<Duration>,
+  ) -> Self {
+    let showcase = matches!(options.mode, Mode::Demo).then(|| {
+      Showcase::new(
+        StdRng::seed_from_u64(DEMO_SEED),
+        Some(grid.clone()),
+        Instant::now() + SHOWCASE_REST,
+      )
+    });
+    let attract = matches!(options.mode, Mode::Attract).then(|| Attract {
+      last: None,
+      next_move: Instant::now() + ATTRACT_PACE,
+      saved: None,
+    });
+    let (kind, count) = match options.mode {
+      Mode::Tournament(count) => (
+        TournamentKind::Seeds {
+          seed: options.seed,
+          size: options.size,
+        },
+        count,
+      ),
+      Mode::Campaign(max_size) => (TournamentKind::Sizes, max_size + 1 - CAMPAIGN_START),
+      Mode::Daily(date) => (
+        TournamentKind::Daily {
+          date,
+          size: options.size,
+        },
+        1,
+      ),
+      _ => (TournamentKind::Sizes, 0),
+    };
+    let tournament = (count > 0).then(|| Tournament {
+      kind,
+      count,
+      current: 0,
+      moves: 0,
+      started: Instant::now(),
+      result: None,
+      best: BestTournaments::load(),
+    });
+    let layout = LayoutConfig::for_size(options.size, theme.tile_style);
+    let mut app = App {
+      start: grid.clone(),
+      grid,
+      keys,
+      theme,
+      themes: VecDeque::new(),
+      color: options.color,
+      difficulty: options.difficulty,
+      min_hard_moves,
+      start_moves: None,
+      moves: 0,
+      key_presses: 0,
+      direction_moves: HashMap::new(),
+      move_metric,
+      started: Instant::now(),
+      paused: None,
+      escape_pause: false,
+      paused_time: Duration::ZERO,
+      solve_time: None,
+      solved: Vec::new(),
+      history_view: None,
+      browser: None,
+      best: BestMoves::load(),
+      runs: BestRuns::load(),
+      run: Vec::new(),
+      ghost: options.ghost,
+      reference: None,
+      recent: RecentSolves::load(),
+      advise: options.advise,
+      advice: None,
+      history: Vec::new(),
+      undo_limit: options.undo_limit,
+      undos_used: 0,
+      checkpoint: None,
+      checkpoint_used: false,
+      message: None,
+      flash_illegal_moves: options.flash_illegal_moves,
+      flash: None,
+      coach: options.coach,
+      locked: vec![false; options.size * options.size],
+      auto_lock: options.auto_lock,
+      row_assist: options.row_assist,
+      assisted: false,
+      studying: false,
+      studied: false,
+      coach_note: None,
+      track_optimal: options.track_optimal,
+      solutions: HashMap::new(),
+      worker: Worker::start(),
+      hint_wanted: false,
+      attract,
+      idle_attract,
+      move_cap: None,
+      last_input: Instant::now(),
+      last_move: Instant::now(),
+      showcase,
+      spectate: options.spectate.map(Spectate::start),
+      tournament,
+      show_goals: false,
+      show_inversions: false,
+      show_instructions: true,
+      show_idle: false,
+      rotation: 0,
+      padding: 0,
+      reveal_on_hover: options.reveal_on_hover,
+      hover: None,
+      scroll: (0, 0),
+      followed: None,
+      screen: Rect::default(),
+      confirm_quit: ConfirmQuit::default(),
+      escape: Escape::default(),
+      win_screen: WinScreen::default(),
+      movement: Movement::default(),
+      summary: false,
+      sharing: false,
+      quitting: false,
+      animate: options.animate,
+      slide_animation: None,
+      peek: None,
+      show_lines: false,
+      show_ghosts: false,
+      trail: None,
+      blank_target: None,
+      layout,
+      align: options.align,
+      title: options.set_title.then(String::new),
+      exit: false,
+    };
+    app.reset_optimal();
+    app.check_solved_start();
+    app
+  }
+
+  /// Keep the solution new_grid found for a board while shuffling it, so it isn't
+  /// solved again, and show how long it is if that's where the puzzle starts.
+  pub fn keep_start_solution(&mut self, grid: Grid, solution: Vec<Direction>) {
+    self.solutions.insert(grid, solution);
+    self.measure_start();
+  }
+
+  /// Replace the grid with a new randomly shuffled grid.
+  fn shuffle(&mut self) {
+    let shuffled = new_grid(
+      self.grid.size(),
+      self.grid.goal(),
+      self.difficulty,
+      self.min_hard_moves,
+    );
+    self.start_shuffled(shuffled);
+  }
+
+  /// Start playing a grid from new_grid, along with its solution if it was solved to
+  /// make sure it's hard enough.
+  fn start_shuffled(&mut self, (grid, solution): (Grid, Option<Vec<Direction>>)) {
+    if let Some(solution) = solution {
+      self.solutions.insert(grid.clone(), solution);
+    }
+    self.start_puzzle(grid);
+    self.measure_start();
+  }
+
+  /// Show how long the starting position takes to solve, if it was shuffled to a
+  /// minimum length and so has been solved already.
+  fn measure_start(&mut self) {
+    if let (Difficulty::Hard, Some(_)) = (self.difficulty, self.min_hard_moves) {
+      self.start_moves = self.solutions.get(&self.start).map(Vec::len);
+    }
+  }
+
+  /// Start playing the given grid from scratch.
+  fn start_puzzle(&mut self, grid: Grid) {
+    self.layout = LayoutConfig::for_size(grid.size(), self.theme.tile_style);
+    self.grid = grid;
+    self.start = self.grid.clone();
+    self.start_moves = None;
+    self.moves = 0;
+    self.key_presses = 0;
+    self.direction_moves.clear();
+    self.summary = false;
+    self.started = Instant::now();
+    self.last_move = Instant::now();
+    self.paused = self.paused.map(|_| Instant::now());
+    self.paused_time = Duration::ZERO;
+    self.solve_time = None;
+    self.advice = None;
+    self.history.clear();
+    self.run.clear();
+    if let Some(trail) = &mut self.trail {
+      trail.clear();
+    }
+    self.undos_used = 0;
+    self.locked = vec![false; self.grid.size() * self.grid.size()];
+    self.checkpoint = None;
+    self.checkpoint_used = false;
+    self.assisted = false;
+    self.studying = false;
+    self.studied = false;
+    self.reset_optimal();
+    self.check_solved_start();
+  }
+
+  /// A puzzle that starts out solved is won already, with no moves and no time taken.
+  /// It's not worth recording anywhere.
+  fn check_solved_start(&mut self) {
+    if self.start.is_solved() {
+      self.solve_time = Some(Duration::ZERO);
+      self.message = Some("This puzzle starts out solved".to_string());
+    }
+  }
+
+  /// How many more undos the player can use, or None if they're unlimited.
+  fn undos_remaining(&self) -> Option<u32> {
+    self
+      .undo_limit
+      .map(|limit| limit.saturating_sub(self.undos_used))
+  }
+
+  /// Take back the most recent move, if there is one and the undo limit allows it.
+  fn undo(&mut self) {
+    self.rewind(1);
+  }
+
+  /// Take back up to the given number of moves as a single undo, stopping at the start
+  /// of the puzzle.
+  fn rewind(&mut self, moves: usize) {
+    if self.history.is_empty() {
+      self.message = Some("Nothing to undo".to_string());
+      return;
+    }
+    if self.undos_remaining() == Some(0) {
+      self.message = Some("No undos left".to_string());
+      return;
+    }
+    if moves > self.history.len() && moves > 1 {
+      self.message = Some("Back at the start".to_string());
+    }
+    for _ in 0..moves.min(self.history.len()) {
+      self.take_back();
+    }
+    if self.history.is_empty()
+      && let Some(trail) = &mut self.trail
+    {
+      // Back at the start, so there's no path to show.
+      trail.clear();
+    }
+    self.undos_used += 1;
+    self.unlock_moved();
+    self.reset_optimal();
+  }
+
+  /// Remember where the player is, so they can come back to try again from here.
+  fn set_checkpoint(&mut self) {
+    self.checkpoint = Some(self.history.len());
+    self.message = Some("Checkpoint set".to_string());
+  }
+
+  /// Go back to the checkpoint, taking back every move since it was set.
+  fn return_to_checkpoint(&mut self) {
+    let Some(checkpoint) = self.checkpoint else {
+      self.message = Some("No checkpoint set".to_string());
+      return;
+    };
+    while self.history.len() > checkpoint {
+      self.take_back();
+    }
+    self.checkpoint_used = true;
+    self.unlock_moved();
+    self.message = Some("Back to checkpoint".to_string());
+    self.reset_optimal();
+  }
+
+  /// Save the start position and every move since to a file, so they can be played back
+  /// with --import or shared.
+  fn export(&mut self) {
+    let list = MoveList {
+      start: Some(self.start.clone()),
+      moves: self
+        .history
+        .iter()
+        .flat_map(|m| std::iter::repeat_n(m.direction, m.slides as usize))
+        .collect(),
+    };
+    let Some(dir) = data_dir().map(|dir| dir.join("moves")) else {
+      self.message = Some("Nowhere to save moves: HOME isn't set".to_string());
+      return;
+    };
+    let path = dir.join(format!("{}.txt", self.start.code()));
+    let result = std::fs::create_dir_all(&dir).and_then(|()| std::fs::write(&path, list.text()));
+    self.message = Some(match result {
+      Ok(()) => format!("Saved moves to {}", path.display()),
+      Err(err) => format!("Couldn't save moves: {err}"),
+    });
+  }
+
+  /// Undoing ignores locks, so let go of any locked cells that no longer hold their tile.
+  fn unlock_moved(&mut self) {
+    for (locked, placed) in self.locked.iter_mut().zip(self.grid.placed()) {
+      *locked &= placed;
+    }
+  }
+
+  /// Reverse the last move in the history.
+  fn take_back(&mut self) {
+    let Some(last) = self.history.pop() else {
+      return;
+    };
+    // A whole row slid at once comes back in one go, too.
+    for _ in 0..last.slides {
+      self.make_move(last.direction.opposite());
+    }
+    if self
+      .checkpoint
+      .is_some_and(|checkpoint| checkpoint > self.history.len())
+    {
+      // The checkpoint was set after a move that's no longer there.
+      self.checkpoint = None;
+    }
+  }
+
+  /// Whether the puzzle's been played without undos, checkpoints or help, so a win
+  /// counts as clean.
+  fn clean_solve(&self) -> bool {
+    self.undos_used == 0 && !self.checkpoint_used && !self.assisted && !self.studied
+  }
+
+  /// How many tiles slid each way, for players who like to look back over a solve.
+  fn direction_counts(&self) -> String {
+    let count = |direction| self.direction_moves.get(&direction).copied().unwrap_or(0);
+    format!(
+      "↑{} ↓{} ←{} →{}",
+      count(Direction::Up),
+      count(Direction::Down),
+      count(Direction::Left),
+      count(Direction::Right)
+    )
+  }
+
+  /// Check if the puzzle is in a winning state.
+  fn is_win(&self) -> bool {
+    self.grid.is_solved()
+  }
+
+  /// The fraction of tiles that are in their goal positions, from 0.0 to 1.0.
+  fn progress(&self) -> f64 {
+    self.grid.correct_tiles() as f64 / self.grid.tile_count() as f64
+  }
+
+  /// Make a move on the player's behalf, remembering it so it can be undone.
+  fn player_move(&mut self, direction: Direction) {
+    self.player_slide(direction, 1);
+  }
+
+  /// Slide up to the given number of tiles in one direction for a single key press,
+  /// stopping at the edge of the board or at a locked tile.
+  fn player_slide(&mut self, direction: Direction, max: usize) {
+    // Any move, like a click, drops a cell picked for the blank and its walk, and a
+    // hint for the board as it was.
+    self.blank_target = None;
+    self.hint_wanted = false;
+    let distance = self.grid.manhattan_distance();
+    let mut slides = 0;
+    // The cells the moving tiles end up in, which is everywhere the blank was.
+    let mut cells = Vec::new();
+    while slides < max {
+      if self.is_locked(direction) {
+        self.message = Some(format!(
+          "That tile is locked (Shift+{} unlocks)",
+          self.keys.lock.to_ascii_uppercase()
+        ));
+        break;
+      }
+      let blank = self.grid.find_blank();
+      let on_path = self
+        .solutions
+        .get(&self.grid)
+        .map(|path| path.first() == Some(&direction));
+      if !self.make_move(direction) {
+        break;
+      }
+      cells.push(blank);
+      slides += 1;
+      // Each tile of a whole row or column counts, like it does in `moves`.
+      *self.direction_moves.entry(direction).or_default() += 1;
+      if self.track_optimal && on_path == Some(false) {
+        self.message = Some("Off the optimal path".to_string());
+      }
+    }
+    if self.auto_lock {
+      self.lock_placed();
+    }
+    self.reset_optimal();
+    if slides == 0 {
+      if self.flash_illegal_moves {
+        self.flash = Some((direction, Instant::now() + FLASH_DURATION));
+      }
+      return;
+    }
+    // Only whole rows and columns slide smoothly, so it's clear they moved together.
+    // A new key press cuts short any slide that's still going.
+    self.slide_animation = (self.animate && max > 1).then(|| SlideAnimation {
+      direction,
+      cells,
+      started: Instant::now(),
+    });
+    self.history.push(Move {
+      direction,
+      slides: slides as u32,
+    });
+    self.moves += slides as u32;
+    self.key_presses += 1;
+    self.last_move = Instant::now();
+    if self.coach && self.grid.manhattan_distance() > distance {
+      self.coach_note = Some(Instant::now() + COACH_NOTE_DURATION);
+    }
+  }
+
+  /// Whether the tile that would slide in the given direction is locked.
+  fn is_locked(&self, direction: Direction) -> bool {
+    self
+      .grid
+      .tile_to_slide(direction)
+      .is_some_and(|(x, y)| self.locked[y * self.grid.size() + x])
+  }
+
+  /// Lock every tile that's in its goal position.
+  fn lock_placed(&mut self) {
+    for (locked, placed) in self.locked.iter_mut().zip(self.grid.placed()) {
+      *locked |= placed;
+    }
+  }
+
+  /// How long the player has spent on this puzzle, stopping once it's solved.
+  fn elapsed(&self) -> Duration {
+    self
+      .solve_time
+      .unwrap_or_else(|| self.started.elapsed().saturating_sub(self.pause_so_far()))
+  }
+
+  /// How long the current pause has gone on, if the clock is stopped.
+  fn pause_so_far(&self) -> Duration {
+    self.paused.map_or(Duration::ZERO, |since| since.elapsed())
+  }
+
+  /// Stop the clock until `resume`.
+  fn pause(&mut self) {
+    if self.paused.is_none() {
+      self.paused = Some(Instant::now());
+    }
+  }
+
+  /// Start the clock again, leaving out the time spent paused. The clock stays stopped
+  /// while attract mode is standing in for the player's game.
+  fn resume(&mut self) {
+    if self
+      .attract
+      .as_ref()
+      .is_some_and(|attract| attract.saved.is_some())
+    {
+      return;
+    }
+    let Some(since) = self.paused.take() else {
+      return;
+    };
+    self.escape_pause = false;
+    let pause = since.elapsed();
+    self.started += pause;
+    self.paused_time += pause;
+    if let Some(tournament) = &mut self.tournament
+      && tournament.result.is_none()
+    {
+      tournament.started += pause;
+    }
+  }
+
+  /// What this puzzle's records are kept under.
+  fn record_key(&self) -> String {
+    record_key(&self.start.code(), self.start.goal(), self.move_metric)
+  }
+
+  /// The number of moves the current solve is scored by.
+  fn score(&self) -> u32 {
+    match self.move_metric {
+      MoveMetric::Slides => self.moves,
+      MoveMetric::KeyPresses => self.key_presses,
+    }
+  }
+
+  /// Save the number of moves used if it's a new best for this starting position.
+  fn record_win(&mut self) {
+    if self.start.is_solved() {
+      return;
+    }
+    if self.studied {
+      // elapsed() stops at the solve time once there is one.
+      self.solve_time = Some(self.elapsed());
+      return;
+    }
+    let code = self.start.code();
+    if self.solve_time.is_none() {
+      let time = self.elapsed();
+      self.solve_time = Some(time);
+      self.solved.push(Solved {
+        code: code.clone(),
+        moves: self.score(),
+        time,
+      });
+      let size = self.grid.size();
+      let difficulty = self.difficulty.id().to_lowercase();
+      let clean = self.clean_solve();
+      if let Err(err) = self
+        .recent
+        .record(size, time, self.score(), &difficulty, clean)
+      {
+        self.message = Some(format!("Couldn't save your time: {err}"));
+      }
+      if self.advise && !self.in_tournament() {
+        self.advice = self
+          .difficulty
+          .advice(size, &self.recent.moves(size, &difficulty));
+      }
+    }
+    let key = self.record_key();
+    let previous = self.best.get(&key);
+    let score = self.score();
+    let result = self.best.record(&key, score).and_then(|best| {
+      if best {
+        self.runs.record(&key, std::mem::take(&mut self.run))?;
+      }
+      Ok(best)
+    });
+    match result {
+      Ok(true) if previous.is_some() => {
+        self.message = Some(format!("New best: {score} moves!"));
+      }
+      Ok(_) => {}
+      Err(err) => {
+        self.message = Some(format!("Couldn't save your best: {err}"));
+      }
+    }
+  }
+
+  /// After a win in a tournament, count the puzzle's moves and go on to the next one,
+  /// or work out the combined result if that was the last.
+  fn advance_tournament(&mut self) {
+    let goal = self.grid.goal();
+    let difficulty = self.difficulty;
+    let score = self.score();
+    let Some(tournament) = &mut self.tournament else {
+      return;
+    };
+    if tournament.result.is_some() {
+      return;
+    }
+    tournament.moves += score;
+    tournament.current += 1;
+    if tournament.current < tournament.count {
+      let (size, seed) = tournament.puzzle(tournament.current);
+      self.start_tournament_puzzle(size, seed);
+      return;
+    }
+    let result = TournamentResult {
+      moves: tournament.moves,
+      time: tournament.started.elapsed(),
+    };
+    tournament.result = Some(result);
+    let name = tournament.name(goal, difficulty);
+    let previous = tournament.best.get(&name);
+    match tournament.best.record(&name, result) {
+      Ok(true) if previous.is_some() => {
+        self.message = Some(format!("New best {}!", tournament.title()));
+      }
+      Ok(_) => {}
+      Err(err) => {
+        self.message = Some(format!("Couldn't save your result: {err}"));
+      }
+    }
+  }
+
+  /// Start the tournament over from its first puzzle.
+  fn restart_tournament(&mut self) {
+    let Some(tournament) = &mut self.tournament else {
+      return;
+    };
+    tournament.current = 0;
+    tournament.moves = 0;
+    tournament.started = Instant::now();
+    tournament.result = None;
+    let (size, seed) = tournament.puzzle(0);
+    self.start_tournament_puzzle(size, seed);
+  }
+
+  /// Start one of a tournament's puzzles: the seeded one if it has a seed, or a fresh
+  /// shuffle if not.
+  fn start_tournament_puzzle(&mut self, size: usize, seed: Option<u64>) {
+    let goal = self.grid.goal();
+    match seed {
+      Some(seed) => self.start_puzzle(seeded_grid(size, goal, self.difficulty, seed)),
+      None => {
+        let shuffled = new_grid(size, goal, self.difficulty, self.min_hard_moves);
+        self.start_shuffled(shuffled);
+      }
+    }
+  }
+
+  /// Whether a tournament is underway, which rules out switching to other puzzles.
+  fn in_tournament(&self) -> bool {
+    self
+      .tournament
+      .as_ref()
+      .is_some_and(|tournament| tournament.result.is_none())
+  }
+
+  /// Make a move if possible. If the given direction doesn't work, do nothing.
+  /// Returns whether a tile moved.
+  fn make_move(&mut self, direction: Direction) -> bool {
+    let blank = self.grid.find_blank();
+    let before = self.grid.clone();
+    if !self.grid.slide(direction) {
+      return false;
+    }
+    self.carry_solution(&before, direction);
+    let time = self.elapsed();
+    self.run.push((time, direction));
+    if let Some(trail) = &mut self.trail {
+      trail.push_front((blank, Instant::now()));
+      trail.truncate(TRAIL_LENGTH);
+    }
+    true
+  }
+
+  /// Keep what's left of the solution for the board before a move, if the move was the
+  /// first of it, so following a solution never has to solve the board again.
+  fn carry_solution(&mut self, before: &Grid, direction: Direction) {
+    if self.solutions.contains_key(&self.grid) {
+      return;
+    }
+    if let Some([first, rest @ ..]) = self.solutions.get(before).map(Vec::as_slice)
+      && *first == direction
+    {
+      let rest = rest.to_vec();
+      self.solutions.insert(self.grid.clone(), rest);
+    }
+  }
+
+  /// A solution for the board as it is now, or an empty one if it can't be solved.
+  /// None while it's still being worked out.
+  fn solution(&mut self) -> Option<Vec<Direction>> {
+    if let Some(path) = self.solutions.get(&self.grid) {
+      return Some(path.clone());
+    }
+    self.worker.solve(&self.grid);
+    None
+  }
+
+  /// Start working out the board's solution, if it's shown while tracking the optimal
+  /// path or studying.
+  fn reset_optimal(&mut self) {
+    if self.track_optimal || self.studying {
+      self.solution();
+    }
+  }
+
+  /// Keep any solutions the worker has finished, and give a hint that was waiting on
+  /// one.
+  fn receive_solutions(&mut self) {
+    let mut received = false;
+    while let Ok((grid, path)) = self.worker.solutions.try_recv() {
+      self.keep_solution(grid, path);
+      received = true;
+    }
+    if self.hint_wanted && self.solutions.contains_key(&self.grid) {
+      self.hint();
+    }
+    if received {
+      // The worker passes over boards for newer ones, so ask again for any still
+      // showing.
+      if self.hint_wanted {
+        self.solution();
+      }
+      self.reset_optimal();
+      self.preview_seed();
+    }
+  }
+
+  /// Remember a solution the worker found.
+  fn keep_solution(&mut self, grid: Grid, path: Vec<Direction>) {
+    if self.worker.solving.as_ref() == Some(&grid) {
+      self.worker.solving = None;
+    }
+    self.solutions.entry(grid).or_insert(path);
+  }
+
+  /// Make the puzzle for the browser's seed, unless that's already been done, and start
+  /// working out how long it takes to solve.
+  fn preview_seed(&mut self) {
+    let size = self.grid.size();
+    let goal = self.grid.goal();
+    let difficulty = self.difficulty;
+    let Some(browser) = &mut self.browser else {
+      return;
+    };
+    let grid = browser
+      .previews
+      .entry(browser.seed)
+      .or_insert_with(|| seeded_grid(size, goal, difficulty, browser.seed));
+    if !self.solutions.contains_key(grid) {
+      self.worker.solve(grid);
+    }
+  }
+
+  /// What to show while the player picks a cell for the blank: how long the walk there
+  /// is, which the board shows too.
+  fn blank_walk_message(&self, target: (usize, usize)) -> String {
+    match self.blank_walk(target).len() {
+      0 if target == self.grid.find_blank() => {
+        "Pick where the blank goes, then press Enter".to_string()
+      }
+      0 => "The blank can't get there without moving a locked tile".to_string(),
+      1 => "1 move to get there: Enter walks the blank".to_string(),
+      n => format!("{n} moves to get there: Enter walks the blank"),
+    }
+  }
+
+  /// Walk the blank to the given cell, going around tiles that are already in place if
+  /// there's a way to. The whole walk counts as a single key press.
+  fn walk_blank(&mut self, target: (usize, usize)) {
+    let path = self.blank_walk(target);
+    if path.is_empty() {
+      return;
+    }
+    let key_presses = self.key_presses;
+    for direction in path {
+      self.player_move(direction);
+    }
+    self.key_presses = key_presses + 1;
+  }
+
+  /// The moves that walk the blank to the given cell, going around tiles that are
+  /// already in place if there's a way to.
+  fn blank_walk(&self, target: (usize, usize)) -> Vec<Direction> {
+    // Locked tiles can't be moved, so the fallback still has to go around those.
+    solver::blank_path(&self.grid, target, &self.grid.placed())
+      .or_else(|| solver::blank_path(&self.grid, target, &self.locked))
+      .unwrap_or_default()
+  }
+
+  /// The cells the blank passes through on its walk to the given cell, not counting
+  /// where it starts, so the walk can be shown before it's made.
+  fn blank_walk_cells(&self, target: (usize, usize)) -> Vec<(usize, usize)> {
+    let (mut x, mut y) = self.grid.find_blank();
+    self
+      .blank_walk(target)
+      .into_iter()
+      .map(|direction| {
+        let (dx, dy) = direction.offset();
+        x = x.wrapping_add_signed(dx);
+        y = y.wrapping_add_signed(dy);
+        (x, y)
+      })
+      .collect()
+  }
+
+  /// The row the row assist can finish: the first one that isn't done, if it's one
+  /// tile short and has two more rows below it to make room.
+  fn assist_row(&self) -> Option<usize> {
+    let size = self.grid.size();
+    let y = (0..size).find(|&y| !self.grid.row_complete(y))?;
+    let placed = self.grid.placed();
+    let missing = placed[y * size..(y + 1) * size]
+      .iter()
+      .filter(|&&placed| !placed)
+      .count();
+    (missing == 1 && y + 2 < size).then_some(y)
+  }
+
+  /// Make the last few moves of a row that's one tile short, for a player who's stuck
+  /// on it. The moves count like the player's own, and the whole lot as one key press.
+  fn finish_row(&mut self) {
+    if !self.row_assist {
+      self.message = Some("Row assists are off (--row-assist turns them on)".to_string());
+      return;
+    }
+    let Some(y) = self.assist_row() else {
+      self.message = Some("No row is one tile short".to_string());
+      return;
+    };
+    let Some(path) = solver::finish_row(&self.grid, y, &self.locked) else {
+      self.message = Some(format!(
+        "Can't finish row {} without moving locked tiles",
+        y + 1
+      ));
+      return;
+    };
+    let key_presses = self.key_presses;
+    for direction in path {
+      self.player_move(direction);
+    }
+    self.key_presses = key_presses + 1;
+    self.assisted = true;
+    self.message = Some(format!("Row {} finished for you", y + 1));
+  }
+
+  /// Step through the solution one move per key press. The first press starts a study
+  /// and shows the move it'll make, and each press after makes it.
+  fn study_step(&mut self) {
+    if self.in_tournament() {
+      self.message = Some("No studying during a tournament".to_string());
+      return;
+    }
+    if self.is_win() {
+      self.message = Some("Already solved!".to_string());
+      return;
+    }
+    let solution = self.solution();
+    if solution.as_ref().is_some_and(Vec::is_empty) {
+      self.message = Some("This puzzle can't be solved".to_string());
+      return;
+    }
+    if !self.studying {
+      self.studying = true;
+      self.studied = true;
+      return;
+    }
+    let Some(&direction) = solution.as_ref().and_then(|path| path.first()) else {
+      self.message = Some("Still working out the solution".to_string());
+      return;
+    };
+    self.player_move(direction);
+    if self.is_win() {
+      self.studying = false;
+      self.message = Some("That's the whole solution".to_string());
+    }
+  }
+
+  /// Work out the next move toward solving the puzzle and suggest it to the player. If
+  /// it takes a while, the hint comes once it's ready.
+  fn hint(&mut self) {
+    self.hint_wanted = false;
+    let message = if self.is_win() {
+      "Already solved!".to_string()
+    } else {
+      match self.solution().map(|path| path.first().copied()) {
+        Some(Some(direction)) => format!(
+          "Hint: {}",
+          self.movement.describe(direction.rotated(self.rotation))
+        ),
+        Some(None) => "This puzzle can't be solved".to_string(),
+        None => {
+          self.hint_wanted = true;
+          "Working out a hint...".to_string()
+        }
+      }
+    };
+    self.message = Some(message);
+  }
+
+  pub fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+    while !self.exit {
+      self.screen = terminal.draw(|frame| self.draw(frame))?.area;
+      self.update_title()?;
+      let timeout = if self.slide_animation.is_some() {
+        FRAME
+      } else {
+        TICK
+      };
+      if event::poll(timeout)? {
+        self.handle_input()?;
+      }
+      self.tick();
+    }
+    Ok(())
+  }
+
+  /// Show the game's status in the terminal's title, if it's changed since last time.
+  fn update_title(&mut self) -> io::Result<()> {
+    let Some(current) = &self.title else {
+      return Ok(());
+    };
+    let status = if self.attract.is_some() || self.showcase.is_some() {
+      "Slyde".to_string()
+    } else if self.is_win() {
+      "Slyde — Solved!".to_string()
+    } else {
+      format!("Slyde — {} moves", self.score())
+    };
+    if *current != status {
+      title::set(&status)?;
+      self.title = Some(status);
+    }
+    Ok(())
+  }
+
+  /// Update anything that changes with time rather than input.
+  fn tick(&mut self) {
+    self.idle();
+    self.follow_blank();
+    if self.flash.is_some_and(|(_, until)| Instant::now() >= until) {
+      self.flash = None;
+    }
+    if self.coach_note.is_some_and(|until| Instant::now() >= until) {
+      self.coach_note = None;
+    }
+    if self.peek.is_some_and(|until| Instant::now() >= until) {
+      self.peek = None;
+    }
+    if self
+      .slide_animation
+      .as_ref()
+      .is_some_and(|animation| animation.progress() >= 1.0)
+    {
+      self.slide_animation = None;
+    }
+    if let Some(trail) = &mut self.trail {
+      trail.retain(|&(_, left)| left.elapsed() < TRAIL_FADE);
+    }
+    let pace = self.attract_pace();
+    if let Some(attract) = &mut self.attract
+      && Instant::now() >= attract.next_move
+    {
+      let direction = random_move(&self.grid, attract.last, &mut rng());
+      self.grid.slide(direction);
+      attract.last = Some(direction);
+      attract.next_move = Instant::now() + pace;
+    }
+    if self
+      .showcase
+      .as_ref()
+      .is_some_and(|showcase| !showcase.paused && Instant::now() >= showcase.next_move)
+    {
+      self.showcase_move();
+    }
+    self.spectate_moves();
+    self.receive_solutions();
+  }
+
+  /// Make any moves that have come in from the file being spectated, and stop once the
+  /// board is solved. The moves aren't the player's, so nothing is recorded.
+  fn spectate_moves(&mut self) {
+    loop {
+      let Some(spectate) = &mut self.spectate else {
+        return;
+      };
+      // With a cap, moves that come in faster wait their turn.
+      if self.move_cap.is_some() && Instant::now() < spectate.next_move {
+        return;
+      }
+      let Ok(letter) = spectate.letters.try_recv() else {
+        return;
+      };
+      if letter.is_whitespace() {
+        continue;
+      }
+      if let Some(cap) = self.move_cap {
+        spectate.next_move = Instant::now() + cap;
+      }
+      match Direction::from_letter(letter) {
+        Some(direction) => self.player_move(direction),
+        None => self.message = Some(format!("Skipped {letter:?}, which isn't a move")),
+      }
+      if self.is_win() {
+        self.spectate = None;
+        self.message = Some("Solved by the spectated program".to_string());
+        return;
+      }
+    }
+  }
+
+  /// Switch to the next theme, back around to the first after the last.
+  fn next_theme(&mut self) {
+    let Some(next) = self.themes.pop_front() else {
+      self.message = Some(format!(
+        "No other themes to switch to from {}",
+        self.theme.name
+      ));
+      return;
+    };
+    let previous = std::mem::replace(&mut self.theme, next);
+    self.themes.push_back(previous);
+    self.message = Some(format!("Theme: {}", self.theme.name));
+  }
+
+  /// Turn the board's view the given number of quarter turns clockwise.
+  fn rotate(&mut self, turns: u8) {
+    self.rotation = (self.rotation + turns) % 4;
+  }
+
+  /// Where a cell of the grid shows on the turned board.
+  fn view_cell(&self, cell: (usize, usize)) -> (usize, usize) {
+    rotate_cell(self.grid.size(), cell, self.rotation)
+  }
+
+  /// The move that looks like the given direction on the turned board, so the keys move
+  /// tiles the way they point on screen.
+  fn move_shown_as(&self, direction: Direction) -> Direction {
+    direction.rotated(4 - self.rotation)
+  }
+
+  /// How many columns and rows of tiles fit on screen, as of the last draw. A board too
+  /// big for the terminal shows part of itself at a time.
+  fn view_size(&self) -> (usize, usize) {
+    let size = self.grid.size();
+    if self.screen.is_empty() {
+      // Not drawn yet, so there's nothing to go by.
+      return (size, size);
+    }
+    let area = self.padded(self.screen);
+    let columns = (1..=size)
+      .rev()
+      .find(|&n| {
+        let board = self
+          .layout
+          .board_area(Rect::default(), n as u16, Align::Left);
+        board.right() <= area.width
+      })
+      .unwrap_or(1);
+    let rows = (1..=size)
+      .rev()
+      .find(|&n| self.layout.main_height(n as u16) + self.lines_around() <= area.height)
+      .unwrap_or(1);
+    (columns, rows)
+  }
+
+  /// The first column and row of tiles on screen, never scrolled past the board's edge.
+  fn scroll(&self) -> (usize, usize) {
+    let size = self.grid.size();
+    let (columns, rows) = self.view_size();
+    (
+      self.scroll.0.min(size - columns),
+      self.scroll.1.min(size - rows),
+    )
+  }
+
+  /// Scroll just far enough to bring the blank back in sight, if it's moved since last
+  /// time.
+  fn follow_blank(&mut self) {
+    let blank = self.view_cell(self.grid.find_blank());
+    if self.followed == Some(blank) {
+      return;
+    }
+    self.followed = Some(blank);
+    let (columns, rows) = self.view_size();
+    let (x, y) = self.scroll();
+    let follow =
+      |start: usize, shown: usize, at: usize| start.min(at).max((at + 1).saturating_sub(shown));
+    self.scroll = (follow(x, columns, blank.0), follow(y, rows, blank.1));
+  }
+
+  /// Look at another part of a board that doesn't all fit on screen, one tile over in
+  /// the given direction.
+  fn pan(&mut self, direction: Direction) {
+    let (x, y) = self.scroll();
+    self.scroll = match direction {
+      Direction::Up => (x, y.saturating_sub(1)),
+      Direction::Down => (x, y + 1),
+      Direction::Left => (x.saturating_sub(1), y),
+      Direction::Right => (x + 1, y),
+    };
+    // Go through `scroll` to stay within the board.
+    self.scroll = self.scroll();
+  }
+
+  /// Start a showcase, which only makes sense from a solved board.
+  fn start_showcase(&mut self) {
+    if !self.is_win() {
+      self.message = Some("Solve the puzzle first".to_string());
+      return;
+    }
+    let mut showcase = Showcase::new(StdRng::from_rng(&mut rng()), None, Instant::now());
+    showcase.cap = self.move_cap;
+    self.showcase = Some(showcase);
+  }
+
+  /// Turn every overlay on or off at once, to see how long they take to draw.
+  pub fn show_overlays(&mut self, on: bool) {
+    self.show_goals = on;
+    self.show_lines = on;
+    self.show_ghosts = on;
+    self.show_inversions = on;
+    self.show_idle = on;
+    self.trail = on.then(VecDeque::new);
+  }
+
+  /// Cap how fast the board moves by itself, including in a showcase that's already
+  /// playing, like the demo.
+  pub fn set_move_cap(&mut self, cap: Option<Duration>) {
+    self.move_cap = cap;
+    if let Some(showcase) = &mut self.showcase {
+      showcase.cap = cap;
+    }
+  }
+
+  /// How long attract mode waits between moves.
+  fn attract_pace(&self) -> Duration {
+    ATTRACT_PACE.max(self.move_cap.unwrap_or_default())
+  }
+
+  /// Make the next move of the showcase, planning another round when the last one is
+  /// done. A board that isn't solved yet, like the demo's, gets solved before any
+  /// scrambling.
+  fn showcase_move(&mut self) {
+    let Some(showcase) = &mut self.showcase else {
+      return;
+    };
+    if showcase.moves.is_empty() {
+      let mut scrambled = self.grid.clone();
+      if scrambled.is_solved() {
+        let mut last = None;
+        for _ in 0..SHOWCASE_SCRAMBLE {
+          let direction = random_move(&scrambled, last, &mut showcase.rng);
+          scrambled.slide(direction);
+          showcase.moves.push_back(direction);
+          last = Some(direction);
+        }
+      }
+      let solution = solver::solve(&scrambled).expect("Scrambling keeps a board solvable.");
+      showcase.moves.extend(solution);
+    }
+    if let Some(direction) = showcase.moves.pop_front() {
+      self.grid.slide(direction);
+    }
+    showcase.next_move = Instant::now() + showcase.pace();
+  }
+
+  /// End the showcase, putting the board back the way the player left it. The demo
+  /// starts a game on its board instead.
+  fn stop_showcase(&mut self) {
+    match self.showcase.take().and_then(|showcase| showcase.demo) {
+      Some(grid) => self.start_puzzle(grid),
+      None => {
+        self.grid = Grid::solved(self.grid.size(), self.grid.goal().clone());
+        self.reset_optimal();
+      }
+    }
+  }
+
+  /// Leave attract mode. If it started while the player was away, put their game back
+  /// the way they left it. Otherwise let them take over the board where it is.
+  fn stop_attract(&mut self) {
+    match self.attract.take().and_then(|attract| attract.saved) {
+      Some(grid) => {
+        self.grid = grid;
+        self.resume();
+      }
+      None => self.start_puzzle(self.grid.clone()),
+    }
+  }
+
+  /// Start attract mode after a while without input, on a copy of the player's board so
+  /// their game is safe. Only while they're playing, not in the middle of something
+  /// else.
+  fn idle(&mut self) {
+    let Some(idle) = self.idle_attract else {
+      return;
+    };
+    if self.last_input.elapsed() < idle
+      || self.attract.is_some()
+      || self.showcase.is_some()
+      || self.spectate.is_some()
+      || self.browser.is_some()
+      || self.history_view.is_some()
+      || self.blank_target.is_some()
+    {
+      return;
+    }
+    self.attract = Some(Attract {
+      last: None,
+      next_move: Instant::now() + self.attract_pace(),
+      saved: Some(self.grid.clone()),
+    });
+    self.pause();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::config::Defaults;
+  use crate::demo_grid;
+  use crate::keys::Preset;
+
+  /// A game of the grid's size on the grid, with everything else left as it is.
+  pub(super) fn app(grid: Grid) -> App {
+    let options = Options::new(&Defaults {
+      size: grid.size(),
+      difficulty: Difficulty::Easy,
+      keys: Preset::Standard,
+      align: Align::Center,
+    });
+    let mut app = App::new(
+      options,
+      grid,
+      KeyBindings::default(),
+      Theme::default(),
+      MoveMetric::default(),
+      None,
+      None,
+    );
+    // Records are kept in memory, so a test's wins don't end up in the player's files.
+    app.best = BestMoves::default();
+    app.runs = BestRuns::default();
+    app.recent = RecentSolves::default();
+    app
+  }
+
+  /// Wait for the worker to solve the board as it is now.
+  pub(super) fn wait_for_solution(app: &mut App) -> Vec<Direction> {
+    loop {
+      if let Some(path) = app.solution() {
+        return path;
+      }
+      let (grid, path) = app.worker.solutions.recv().unwrap();
+      app.keep_solution(grid, path);
+    }
+  }
+
+  /// Every arrangement of a 2x2 board, as position codes.
+  fn two_by_two_codes() -> Vec<String> {
+    let digits = ['0', '1', '2', '3'];
+    let mut codes = Vec::new();
+    for a in digits {
+      for b in digits {
+        for c in digits {
+          for d in digits {
+            let code = String::from_iter([a, b, c, d]);
+            if Grid::from_code(&code).is_ok() {
+              codes.push(code);
+            }
+          }
+        }
+      }
+    }
+    codes
+  }
+
+  #[test]
+  fn half_of_two_by_two_boards_solve_in_six_moves() {
+    let codes = two_by_two_codes();
+    assert_eq!(codes.len(), 24);
+    let mut solvable = 0;
+    for code in codes {
+      let grid = Grid::from_code(&code).unwrap();
+      let Some(solution) = solver::solve(&grid) else {
+        assert!(!grid.is_solvable(), "{code} wasn't solved");
+        continue;
+      };
+      assert!(grid.is_solvable(), "{code} was solved");
+      assert!(solution.len() <= 6, "{code} took {} moves", solution.len());
+      let mut app = app(grid);
+      for direction in solution {
+        assert!(app.grid.slide(direction));
+      }
+      assert!(app.is_win(), "{code} wasn't won");
+      solvable += 1;
+    }
+    assert_eq!(solvable, 12);
+  }
+
+  #[test]
+  fn hints_follow_the_solution_without_solving_again() {
+    let mut app = app(demo_grid());
+    app.hint();
+    assert_eq!(app.message.as_deref(), Some("Working out a hint..."));
+    let solution = wait_for_solution(&mut app);
+    assert_eq!(solution.len(), 15);
+    app.receive_solutions();
+    assert_eq!(app.message.as_deref(), Some("Hint: slide down"));
+
+    app.player_move(solution[0]);
+    assert_eq!(app.solutions.get(&app.grid), Some(&solution[1..].to_vec()));
+    app.hint();
+    assert_eq!(app.message.as_deref(), Some("Hint: slide right"));
+
+    // Leaving the solution's path leaves nothing to go on.
+    app.player_move(solution[1].opposite());
+    assert!(!app.solutions.contains_key(&app.grid));
+  }
+
+  #[test]
+  fn leaving_the_optimal_path_solves_in_the_background() {
+    let mut app = app(demo_grid());
+    app.track_optimal = true;
+    let solution = wait_for_solution(&mut app);
+    app.player_move(solution[0]);
+    assert_eq!(app.message, None);
+    app.player_move(solution[1].opposite());
+    assert_eq!(app.message.as_deref(), Some("Off the optimal path"));
+    // The move's made straight away, and the board it left is sent off to be solved.
+    assert_eq!(app.worker.solving.as_ref(), Some(&app.grid));
+    // Taking the move back is one more, and there's nothing shorter.
+    let off_path = wait_for_solution(&mut app);
+    assert_eq!(off_path.len(), solution.len());
+  }
+
+  #[test]
+  fn goals_from_the_same_start_keep_their_own_records() {
+    let standard = app(demo_grid());
+    let reverse = app(demo_grid().with_goal(Goal::Reverse));
+    assert_eq!(standard.start.code(), reverse.start.code());
+    assert_ne!(standard.record_key(), reverse.record_key());
+  }
+
+  #[test]
+  fn hard_shuffles_are_measured_without_solving_again() {
+    let mut app = app(Grid::solved(3, Goal::Standard));
+    app.difficulty = Difficulty::Hard;
+    app.min_hard_moves = Some(24);
+    app.track_optimal = true;
+    app.shuffle();
+    assert!(app.start_moves.is_some_and(|moves| moves >= 24));
+    // The solution from shuffling is already there for the optimal path.
+    assert_eq!(app.worker.solving, None);
+    assert_eq!(app.solution().map(|path| path.len()), app.start_moves);
+  }
+
+  #[test]
+  fn seed_previews_are_solved_in_the_background() {
+    let mut app = app(Grid::solved(3, Goal::Standard));
+    app.difficulty = Difficulty::Hard;
+    app.browser = Some(Browser {
+      seed: 0,
+      previews: HashMap::new(),
+    });
+    for seed in 0..4 {
+      app.browser.as_mut().unwrap().seed = seed;
+      app.preview_seed();
+    }
+    // Every seed's puzzle shows straight away, and the worker's on the newest.
+    let previews = &app.browser.as_ref().unwrap().previews;
+    assert_eq!(previews.len(), 4);
+    let newest = previews[&3].clone();
+    assert_eq!(app.worker.solving.as_ref(), Some(&newest));
+    while !app.solutions.contains_key(&newest) {
+      let (grid, path) = app.worker.solutions.recv().unwrap();
+      app.keep_solution(grid, path);
+    }
+    assert_eq!(
+      app.solutions[&newest].len(),
+      solver::solve(&newest).unwrap().len()
+    );
+  }
+
+  #[test]
+  fn solved_start_is_already_won() {
+    let app = app(Grid::solved(4, Goal::Standard));
+    assert!(app.is_win());
+    assert_eq!(app.solve_time, Some(Duration::ZERO));
+    assert_eq!(app.elapsed(), Duration::ZERO);
+    assert_eq!(app.moves, 0);
+    assert!(app.history.is_empty());
+  }
+
+  #[test]
+  fn unsolved_start_is_not_won() {
+    let app = app(demo_grid());
+    assert!(!app.is_win());
+    assert_eq!(app.solve_time, None);
+  }
+}
@@ -0,0 +1,533 @@
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers, MouseEventKind};
+use ratatui::layout::Position;
+
+use crate::REWIND_MOVES;
+use crate::config::{ConfirmQuit, Escape, WinScreen};
+use crate::grid::{Direction, Grid};
+use crate::keys::Action;
+
+use super::{App, Browser};
+
+/// How long a peek at the goal labels lasts after the last press of the peek key, for
+/// terminals that don't say when a key is let go. Holding the key down repeats it
+/// sooner than this, so the labels stay up while it's held.
+const PEEK_DURATION: Duration = Duration::from_millis(600);
+
+impl App {
+  /// Handle a key while the seed browser is showing: the up and down keys go to the
+  /// previous and next seeds, Enter plays the puzzle shown, and anything else closes the
+  /// browser. Returns false if a puzzle was started.
+  fn browse_seeds(&mut self, code: KeyCode) -> bool {
+    let Some(browser) = &mut self.browser else {
+      return true;
+    };
+    match (code, self.keys.action(code)) {
+      (KeyCode::Enter, _) => {
+        let seed = browser.seed;
+        let grid = browser.previews[&seed].clone();
+        self.browser = None;
+        self.start_puzzle(grid);
+        self.message = Some(format!("Seed {seed}"));
+        return false;
+      }
+      (_, Some(Action::Up)) => browser.seed = browser.seed.saturating_sub(1),
+      (_, Some(Action::Down)) => browser.seed = browser.seed.saturating_add(1),
+      _ => {
+        self.browser = None;
+        return true;
+      }
+    }
+    self.preview_seed();
+    true
+  }
+
+  /// Handle a key while the list of solved puzzles is showing: the up and down keys
+  /// move the selection, Enter plays the selected puzzle again, and anything else
+  /// closes the list. Returns false if a puzzle was started.
+  fn browse_history(&mut self, selected: usize, code: KeyCode) -> bool {
+    self.history_view = match (code, self.keys.action(code)) {
+      (KeyCode::Enter, _) => {
+        let Some(solved) = self.solved.get(selected) else {
+          self.history_view = None;
+          return true;
+        };
+        match Grid::from_code(&solved.code) {
+          Ok(grid) => {
+            self.start_puzzle(grid.with_goal(self.grid.goal().clone()));
+            self.history_view = None;
+            return false;
+          }
+          Err(err) => {
+            self.message = Some(format!("Can't play that one again: {err}"));
+            None
+          }
+        }
+      }
+      (_, Some(Action::Up)) => Some(selected.saturating_sub(1)),
+      (_, Some(Action::Down)) => Some((selected + 1).min(self.solved.len().saturating_sub(1))),
+      _ => None,
+    };
+    true
+  }
+
+  /// Handle a key while the player is picking a cell for the blank: the movement keys
+  /// move the pick, Enter walks the blank there, and anything else cancels.
+  fn choose_blank_target(&mut self, (x, y): (usize, usize), code: KeyCode) {
+    let last = self.grid.size() - 1;
+    // The cursor goes the way the key points on screen, however the board's turned.
+    let step = |direction: Direction| {
+      let (dx, dy) = self.move_shown_as(direction).opposite().offset();
+      Some((
+        x.saturating_add_signed(dx).min(last),
+        y.saturating_add_signed(dy).min(last),
+      ))
+    };
+    self.blank_target = match (code, self.keys.action(code)) {
+      (KeyCode::Enter, _) => {
+        self.walk_blank((x, y));
+        None
+      }
+      (_, Some(Action::Up)) => step(Direction::Up),
+      (_, Some(Action::Down)) => step(Direction::Down),
+      (_, Some(Action::Left)) => step(Direction::Left),
+      (_, Some(Action::Right)) => step(Direction::Right),
+      _ => None,
+    };
+    if let Some(target) = self.blank_target {
+      self.message = Some(self.blank_walk_message(target));
+    }
+  }
+
+  /// The move a movement key pointing the given way on screen makes.
+  fn key_move(&self, direction: Direction) -> Direction {
+    self.move_shown_as(self.movement.key_move(direction))
+  }
+
+  /// Apply every key press that's waiting, in order, so fast players don't lose moves
+  /// between redraws.
+  pub(super) fn handle_input(&mut self) -> io::Result<()> {
+    let mut events = vec![event::read()?];
+    while event::poll(Duration::ZERO)? {
+      events.push(event::read()?);
+    }
+    self.handle_events(events);
+    Ok(())
+  }
+
+  /// Act on a batch of events that arrived together. Every key is played, however many
+  /// piled up while the game was busy, but pasted text is ignored.
+  fn handle_events(&mut self, events: Vec<Event>) {
+    let before = self.grid.clone();
+    for event in events {
+      match event {
+        Event::FocusLost => self.pause(),
+        Event::FocusGained => self.resume(),
+        Event::Mouse(event)
+          if matches!(event.kind, MouseEventKind::Moved | MouseEventKind::Drag(_)) =>
+        {
+          self.hover = self.cell_at(event.column, event.row);
+        }
+        Event::Paste(_) => {
+          self.last_input = Instant::now();
+          self.message = Some("Ignored pasted text (--moves plays a list of moves)".to_string());
+        }
+        Event::Key(event) if event.kind == KeyEventKind::Release => {
+          self.release_key(event.code);
+        }
+        // Held keys come as repeats from terminals that report releases, and as more
+        // presses from the rest.
+        Event::Key(event) => {
+          self.last_input = Instant::now();
+          // Some terminals don't say when they get focus back, but a key press means the
+          // player is here.
+          self.resume();
+          // Shift and an arrow looks around a board that doesn't fit on screen. On one
+          // that does, it moves like the arrow alone.
+          if event.modifiers.contains(KeyModifiers::SHIFT)
+            && self.view_size() != (self.grid.size(), self.grid.size())
+            && let Some(direction) = arrow_direction(event.code)
+          {
+            self.pan(direction);
+            continue;
+          }
+          if !self.handle_key(event.code) {
+            break;
+          }
+        }
+        _ => {}
+      }
+    }
+    // Check for a win once the whole batch is in, not partway through it. Any change
+    // counts, so undoing back onto the solution wins too.
+    if self.grid != before && self.is_win() {
+      self.record_win();
+      self.summary = self.win_screen == WinScreen::Summary;
+      self.advance_tournament();
+    }
+  }
+
+  /// Whether quitting now needs the quit key pressed twice. Not on a solved board, or
+  /// before any moves, where there's nothing to lose.
+  fn should_confirm_quit(&self) -> bool {
+    match self.confirm_quit {
+      ConfirmQuit::Always => true,
+      ConfirmQuit::Unsolved => self.moves > 0 && !self.is_win(),
+      ConfirmQuit::Never => false,
+    }
+  }
+
+  /// The board cell at the given terminal position, if there's a tile or the blank
+  /// there.
+  fn cell_at(&self, column: u16, row: u16) -> Option<(usize, usize)> {
+    let size = self.grid.size();
+    let [_, _, main_area, ..] = self.areas(self.padded(self.screen));
+    let board_area = self.board_area(main_area);
+    let position = Position::new(column, row);
+    (0..size)
+      .flat_map(|y| (0..size).map(move |x| (x, y)))
+      .find(|&cell| {
+        self
+          .tile_area(board_area, cell)
+          .is_some_and(|area| area.contains(position))
+      })
+  }
+
+  /// Act on a key being let go, for terminals that say so.
+  fn release_key(&mut self, code: KeyCode) {
+    if self.keys.is_goals_key(code) {
+      self.peek = None;
+    }
+  }
+
+  /// Act on a single key press. Returns false if any keys pressed after it should be
+  /// dropped, because they were meant for a board that's no longer there.
+  fn handle_key(&mut self, code: KeyCode) -> bool {
+    if self.attract.is_some() {
+      // Any key just hands the board over to the player.
+      self.stop_attract();
+      return false;
+    }
+    if let Some(showcase) = &mut self.showcase {
+      if showcase.control(code) {
+        return true;
+      }
+      self.stop_showcase();
+      return false;
+    }
+    if self.spectate.take().is_some() {
+      // Stop following the file and let the player carry on from here.
+      self.message = Some("Stopped spectating".to_string());
+      return false;
+    }
+    self.message = None;
+    if let Some(target) = self.blank_target {
+      self.choose_blank_target(target, code);
+      return true;
+    }
+    if let Some(selected) = self.history_view {
+      return self.browse_history(selected, code);
+    }
+    if self.browser.is_some() {
+      return self.browse_seeds(code);
+    }
+    if std::mem::take(&mut self.sharing) {
+      // Any key puts the board back, and does nothing more.
+      return true;
+    }
+    if std::mem::take(&mut self.summary) {
+      // Any key puts the board back in view, and Enter does nothing more.
+      if code == KeyCode::Enter {
+        return true;
+      }
+    } else if code == KeyCode::Enter && self.is_win() && self.moves > 0 {
+      self.summary = true;
+      return true;
+    }
+    // Terminals send Escape on its own only when it's pressed by itself: as part of a
+    // longer sequence, like an arrow key or Alt and a letter, it arrives as that key.
+    let action = match (code, self.escape) {
+      (KeyCode::Esc, Escape::Quit) => Some(Action::Quit),
+      (KeyCode::Esc, Escape::Pause) if !self.is_win() => {
+        self.pause();
+        self.escape_pause = true;
+        None
+      }
+      _ => self.keys.action(code),
+    };
+    // Any other key takes back a first press of the quit key.
+    let confirmed = std::mem::take(&mut self.quitting);
+    match action {
+      Some(Action::Quit) if !confirmed && self.should_confirm_quit() => {
+        self.quitting = true;
+        self.message = Some(format!(
+          "Press {} again to quit, or any other key to keep playing",
+          self.keys.quit.to_ascii_uppercase()
+        ));
+      }
+      Some(Action::Quit) => {
+        self.exit = true;
+        return false;
+      }
+      Some(Action::Restart) if self.in_tournament() => {
+        // Only the current puzzle starts over. The clock keeps running.
+        self.start_puzzle(self.start.clone());
+        return false;
+      }
+      Some(Action::Restart) if self.tournament.is_some() => {
+        self.restart_tournament();
+        return false;
+      }
+      Some(Action::Restart) => {
+        self.shuffle();
+        return false;
+      }
+      Some(Action::Harder | Action::Browse | Action::History) if self.in_tournament() => {
+        if let Some(tournament) = &self.tournament {
+          self.message = Some(format!("Not during a {}", tournament.title()));
+        }
+      }
+      Some(Action::Harder) => {
+        self.difficulty = self.difficulty.harder();
+        self.shuffle();
+        self.message = Some(format!("Difficulty: {}", self.difficulty.name()));
+        return false;
+      }
+      Some(Action::Undo) => {
+        self.undo();
+      }
+      Some(Action::Rewind) => {
+        self.rewind(REWIND_MOVES);
+      }
+      Some(Action::Checkpoint) => {
+        self.set_checkpoint();
+      }
+      Some(Action::ToCheckpoint) => {
+        self.return_to_checkpoint();
+      }
+      Some(Action::Hint) => {
+        self.hint();
+      }
+      Some(Action::Lock) => {
+        self.lock_placed();
+        self.message = Some("Placed tiles locked".to_string());
+      }
+      Some(Action::Unlock) => {
+        self.locked.fill(false);
+        self.message = Some("All tiles unlocked".to_string());
+      }
+      Some(Action::FinishRow) => {
+        self.finish_row();
+      }
+      Some(Action::MoveBlank) => {
+        let blank = self.grid.find_blank();
+        self.blank_target = Some(blank);
+        self.message = Some(self.blank_walk_message(blank));
+      }
+      Some(Action::ToggleGoals) => {
+        self.show_goals = !self.show_goals;
+      }
+      Some(Action::PeekGoals) => {
+        self.peek = Some(Instant::now() + PEEK_DURATION);
+      }
+      Some(Action::ToggleLines) => {
+        self.show_lines = !self.show_lines;
+      }
+      Some(Action::ToggleGhosts) => {
+        self.show_ghosts = !self.show_ghosts;
+      }
+      Some(Action::ToggleTrail) => {
+        self.trail = match self.trail {
+          Some(_) => None,
+          None => Some(VecDeque::new()),
+        };
+      }
+      Some(Action::ToggleInversions) => {
+        self.show_inversions = !self.show_inversions;
+      }
+      Some(Action::ToggleInstructions) => {
+        self.show_instructions = !self.show_instructions;
+      }
+      Some(Action::ToggleIdle) => {
+        self.show_idle = !self.show_idle;
+      }
+      Some(Action::Browse) => {
+        self.browser = Some(Browser {
+          seed: 0,
+          previews: HashMap::new(),
+        });
+        self.preview_seed();
+      }
+      Some(Action::Export) => {
+        self.export();
+      }
+      Some(Action::Share) => {
+        self.sharing = true;
+      }
+      Some(Action::History) => {
+        // Start with the most recent puzzle selected.
+        self.history_view = Some(self.solved.len().saturating_sub(1));
+      }
+      Some(Action::Showcase) => {
+        self.start_showcase();
+      }
+      Some(Action::NextTheme) => {
+        self.next_theme();
+      }
+      Some(Action::Study) => {
+        self.study_step();
+      }
+      Some(Action::RotateView) => {
+        self.rotate(1);
+      }
+      Some(Action::RotateBack) => {
+        self.rotate(3);
+      }
+      Some(Action::Up) => {
+        self.player_move(self.key_move(Direction::Up));
+      }
+      Some(Action::Down) => {
+        self.player_move(self.key_move(Direction::Down));
+      }
+      Some(Action::Left) => {
+        self.player_move(self.key_move(Direction::Left));
+      }
+      Some(Action::Right) => {
+        self.player_move(self.key_move(Direction::Right));
+      }
+      Some(Action::SlideAll(direction)) => {
+        self.player_slide(self.key_move(direction), self.grid.size());
+      }
+      None => {}
+    }
+    true
+  }
+}
+
+/// The screen direction an arrow key points, if it's an arrow key.
+fn arrow_direction(code: KeyCode) -> Option<Direction> {
+  match code {
+    KeyCode::Up => Some(Direction::Up),
+    KeyCode::Down => Some(Direction::Down),
+    KeyCode::Left => Some(Direction::Left),
+    KeyCode::Right => Some(Direction::Right),
+    _ => None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::app::tests::app;
+  use crate::demo_grid;
+  use crate::keys::KeyBindings;
+  use crate::notation::parse_moves;
+  use crate::solver;
+
+  /// A press of the given key.
+  fn press(code: KeyCode) -> Event {
+    Event::Key(crossterm::event::KeyEvent::new(code, KeyModifiers::NONE))
+  }
+
+  #[test]
+  fn pasted_text_is_not_played() {
+    let mut app = app(demo_grid());
+    app.handle_events(vec![Event::Paste("lrlrlr".to_string())]);
+    assert_eq!(app.grid, demo_grid());
+    assert_eq!(app.moves, 0);
+  }
+
+  /// The arrow key that slides a tile in the given direction.
+  fn arrow(direction: Direction) -> KeyCode {
+    match direction {
+      Direction::Up => KeyCode::Up,
+      Direction::Down => KeyCode::Down,
+      Direction::Left => KeyCode::Left,
+      Direction::Right => KeyCode::Right,
+    }
+  }
+
+  /// All but the last move of the demo's solution, so the board isn't won and nothing
+  /// gets recorded.
+  fn demo_moves() -> Vec<Direction> {
+    parse_moves("DRRULLLDRRRULL").unwrap()
+  }
+
+  #[test]
+  fn every_key_is_played() {
+    let mut expected = demo_grid();
+    for &direction in &demo_moves() {
+      assert!(expected.slide(direction));
+    }
+
+    let mut one_by_one = app(demo_grid());
+    for &direction in &demo_moves() {
+      assert!(one_by_one.handle_key(arrow(direction)));
+    }
+    assert_eq!(one_by_one.grid, expected);
+    assert_eq!(one_by_one.moves, 14);
+
+    // All at once, the way they come in after the game's been busy for a moment.
+    let mut batched = app(demo_grid());
+    batched.handle_events(
+      demo_moves()
+        .into_iter()
+        .map(|direction| press(arrow(direction)))
+        .collect(),
+    );
+    assert_eq!(batched.grid, expected);
+    assert_eq!(batched.moves, 14);
+  }
+
+  #[test]
+  fn bursts_of_keys_are_all_played() {
+    let keys = [KeyCode::Left, KeyCode::Right];
+    let mut app = app(demo_grid());
+    let mut events: Vec<Event> = keys
+      .iter()
+      .cycle()
+      .take(41)
+      .map(|&key| press(key))
+      .collect();
+    events.insert(20, Event::Paste("lrlrlr".to_string()));
+    app.handle_events(events);
+    assert_eq!(app.moves, 41);
+    let mut grid = demo_grid();
+    grid.slide(Direction::Left);
+    assert_eq!(app.grid, grid);
+  }
+
+  /// The demo's solution played in one batch, with the given keys pressed on the
+  /// solution, then moved straight off it again, so the win isn't seen.
+  fn solved_and_left(on_solution: &[KeyCode]) -> App {
+    let solution = solver::solve(&demo_grid()).unwrap();
+    let mut app = app(demo_grid());
+    let mut keys: Vec<KeyCode> = solution.iter().map(|&direction| arrow(direction)).collect();
+    keys.extend(on_solution);
+    keys.push(arrow(solution[solution.len() - 1].opposite()));
+    app.handle_events(keys.into_iter().map(press).collect());
+    assert!(!app.is_win());
+    assert_eq!(app.solve_time, None);
+    app
+  }
+
+  #[test]
+  fn undoing_onto_the_solution_wins() {
+    let mut app = solved_and_left(&[]);
+    app.handle_events(vec![press(KeyCode::Char(app.keys.undo))]);
+    assert!(app.is_win());
+    assert!(app.solve_time.is_some());
+  }
+
+  #[test]
+  fn returning_to_a_checkpoint_on_the_solution_wins() {
+    let checkpoint = KeyBindings::default().checkpoint;
+    let mut app = solved_and_left(&[KeyCode::Char(checkpoint)]);
+    app.handle_events(vec![press(KeyCode::Char(checkpoint.to_ascii_uppercase()))]);
+    assert!(app.is_win());
+    assert!(app.solve_time.is_some());
+  }
+}
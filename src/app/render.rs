@@ -0,0 +1,1078 @@
+use std::time::Duration;
+
+use ratatui::Frame;
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Layout, Margin, Rect};
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Clear, Gauge, Paragraph, Widget, Wrap};
+
+use crate::config::{BorderStyle, DEFAULT_WIN_TEXT, TileStyle};
+use crate::grid::{Direction, Goal};
+use crate::stats::{Run, TREND_SOLVES};
+use crate::status_bar::{Field, StatusBar};
+use crate::{Difficulty, qr, solver};
+
+use super::{App, Browser, TRAIL_FADE, TournamentKind};
+
+/// How many seconds without a move before the idle time stops being grayed out. Any
+/// less is just thinking between moves.
+const IDLE_NOTICE: u64 = 5;
+
+impl App {
+  /// Where the board goes in the main area, sized to the part of it that fits.
+  pub(super) fn board_area(&self, main_area: Rect) -> Rect {
+    let (columns, rows) = self.view_size();
+    self
+      .layout
+      .view_area(main_area, (columns as u16, rows as u16), self.align)
+  }
+
+  /// The area of the tile shown in the given column and row of the turned board, or
+  /// None if it's scrolled out of sight.
+  fn view_tile(&self, board_area: Rect, (x, y): (usize, usize)) -> Option<Rect> {
+    let (columns, rows) = self.view_size();
+    let (scroll_x, scroll_y) = self.scroll();
+    let (x, y) = (x.checked_sub(scroll_x)?, y.checked_sub(scroll_y)?);
+    (x < columns && y < rows).then(|| self.layout.tile(board_area, x, y))
+  }
+
+  /// The area of the tile in the given cell of the grid, or None if it's out of sight.
+  pub(super) fn tile_area(&self, board_area: Rect, cell: (usize, usize)) -> Option<Rect> {
+    self.view_tile(board_area, self.view_cell(cell))
+  }
+
+  /// The area left for the game inside the padding. Without room for the padding
+  /// around the whole board, there's none.
+  pub(super) fn padded(&self, area: Rect) -> Rect {
+    let (width, height) = self
+      .layout
+      .min_size(self.grid.size() as u16, self.lines_around());
+    let room = 2 * u32::from(self.padding);
+    if u32::from(area.width) < u32::from(width) + room
+      || u32::from(area.height) < u32::from(height) + room
+    {
+      return area;
+    }
+    area.inner(Margin::new(self.padding, self.padding))
+  }
+
+  /// How many lines go around the main area: the title, the instructions unless
+  /// they're hidden, the gauge, the status bar and a line of footer.
+  pub(super) fn lines_around(&self) -> u16 {
+    5 + u16::from(self.show_instructions)
+  }
+
+  /// Split the area inside the padding into the title, instructions, main area, gauge,
+  /// status bar and footer, from top to bottom. Hidden instructions get no room at all.
+  pub(super) fn areas(&self, area: Rect) -> [Rect; 6] {
+    let (_, rows) = self.view_size();
+    Layout::vertical([
+      Constraint::Length(2),
+      Constraint::Length(u16::from(self.show_instructions)),
+      Constraint::Length(self.layout.main_height(rows as u16)),
+      Constraint::Length(1),
+      Constraint::Length(1),
+      Constraint::Percentage(100),
+    ])
+    .areas(area)
+  }
+
+  /// A style with the given foreground color, or no style at all if colors are off.
+  fn fg(&self, color: Color) -> Style {
+    if self.color {
+      Style::default().fg(color)
+    } else {
+      Style::default()
+    }
+  }
+
+  pub(super) fn draw(&self, frame: &mut Frame) {
+    frame.render_widget(self, frame.area());
+  }
+
+  /// Whether the number on the tile in the given cell is hidden, for the reveal on
+  /// hover challenge. Everything shows once the puzzle's solved.
+  fn hidden(&self, cell: (usize, usize)) -> bool {
+    self.reveal_on_hover && self.hover != Some(cell) && !self.is_win()
+  }
+
+  /// Draw the seed browser over the board: the seed, its puzzle, and how many moves it
+  /// takes to solve.
+  fn render_browser(&self, browser: &Browser, area: Rect, buf: &mut Buffer) {
+    Clear.render(area, buf);
+    let block = Block::bordered()
+      .title(format!(" Seed {} ", browser.seed).bold())
+      .title_bottom(" Enter: play ");
+    let inner = block.inner(area);
+    block.render(area, buf);
+    let Some(grid) = browser.previews.get(&browser.seed) else {
+      return;
+    };
+    let moves = match self.solutions.get(grid).map(Vec::len) {
+      Some(0) => "Can't be solved".to_string(),
+      Some(moves) if grid.size() <= solver::MAX_OPTIMAL_SIZE => {
+        format!("Solvable in {moves} moves")
+      }
+      Some(moves) => format!("Solvable in about {moves} moves"),
+      None => "Working out how many moves...".to_string(),
+    };
+    let mut lines: Vec<Line> = grid
+      .to_string()
+      .lines()
+      .map(|line| Line::from(line.to_string()))
+      .collect();
+    lines.push(Line::default());
+    lines.push(Line::from(moves));
+    lines.push(Line::from(
+      format!(
+        "Arrows/{}{}: other seeds",
+        self.keys.up.to_ascii_uppercase(),
+        self.keys.down.to_ascii_uppercase()
+      )
+      .dim(),
+    ));
+    Paragraph::new(lines).render(inner, buf);
+  }
+
+  /// Draw the best run from this position as far as it had got at this point in the
+  /// solve, faintly, to race against.
+  fn render_ghost(&self, run: &Run, area: Rect, buf: &mut Buffer) {
+    let elapsed = self.elapsed();
+    let made = run.partition_point(|&(time, _)| time <= elapsed);
+    let mut grid = self.start.clone();
+    for &(_, direction) in &run[..made] {
+      grid.slide(direction);
+    }
+    let block = Block::bordered()
+      .border_style(Style::new().dim())
+      .title(" Best ".dim())
+      .title_bottom(format!(" {made}/{} ", run.len()).dim());
+    let lines: Vec<Line> = grid
+      .to_string()
+      .lines()
+      .map(|line| Line::from(line.to_string()).dim())
+      .collect();
+    Paragraph::new(lines).block(block).render(area, buf);
+  }
+
+  /// Draw the summary of a solve over the board.
+  fn render_summary(&self, area: Rect, buf: &mut Buffer) {
+    Clear.render(area, buf);
+    let block = Block::bordered()
+      .border_style(self.fg(Color::Green))
+      .title(" Solved! ".bold())
+      .title_bottom(" Any key: back ");
+    let inner = block.inner(area);
+    block.render(area, buf);
+    let time = self.elapsed().as_secs();
+    let mut lines = vec![
+      format!("Moves: {}", self.score()),
+      format!("Time: {}:{:02}", time / 60, time % 60),
+      format!("Slides: {}", self.moves),
+      format!("Key presses: {}", self.key_presses),
+      self.direction_counts(),
+    ];
+    if let Some(best) = self.best.get(&self.record_key()) {
+      lines.push(format!("Your best: {best} moves"));
+    }
+    if self.clean_solve() {
+      lines.push("Clean solve!".to_string());
+    }
+    let size = self.grid.size();
+    let clean = self.recent.clean(size);
+    if clean > 0 {
+      lines.push(format!(
+        "Clean: {clean} of your last {} on {size}x{size}",
+        self.recent.count(size)
+      ));
+    }
+    let lines: Vec<_> = lines.into_iter().map(Line::from).collect();
+    Paragraph::new(lines).render(inner.inner(Margin::new(1, 0)), buf);
+  }
+
+  /// Draw the position's code over the board, as a QR code if this build has them and
+  /// it fits, so it can be photographed and played somewhere else.
+  fn render_share(&self, area: Rect, buf: &mut Buffer) {
+    Clear.render(area, buf);
+    let block = Block::bordered()
+      .title(" Share this position ")
+      .title_bottom(" Any key: back ");
+    let inner = block.inner(area);
+    block.render(area, buf);
+    let code = self.grid.code();
+    let mut lines: Vec<Line> = match qr::rows(&code) {
+      Some(rows)
+        if rows.len() + 3 <= usize::from(inner.height)
+          && rows[0].chars().count() <= usize::from(inner.width) =>
+      {
+        // Scanners want a light margin round a dark code, whatever the terminal's
+        // colors are.
+        let style = if self.color {
+          Style::default().fg(Color::White).bg(Color::Black)
+        } else {
+          Style::default()
+        };
+        rows
+          .into_iter()
+          .map(|row| Line::styled(row, style).centered())
+          .collect()
+      }
+      Some(_) => vec![Line::from("Make the terminal bigger to see a QR code").dim()],
+      None => Vec::new(),
+    };
+    lines.push(Line::default());
+    lines.push(Line::from(code).bold().centered());
+    lines.push(
+      Line::from("To play it, --import a move list with a line saying \"start\" and the code")
+        .dim()
+        .centered(),
+    );
+    // Codes for big boards are longer than the screen is wide.
+    Paragraph::new(lines)
+      .wrap(Wrap { trim: false })
+      .render(inner, buf);
+  }
+
+  /// How the latest solves on this size of board compare with the ones before, across
+  /// runs.
+  fn trend_line(&self) -> Line<'static> {
+    let size = self.grid.size();
+    let Some(trend) = self.recent.trend(size) else {
+      let needed = 2 * TREND_SOLVES - self.recent.count(size);
+      return Line::from(format!(
+        "{size}x{size} trend: solve {needed} more to see one"
+      ))
+      .dim();
+    };
+    let (arrow, word) = if trend.recent <= trend.previous {
+      ("↓", "improving")
+    } else {
+      ("↑", "slower")
+    };
+    Line::from(format!(
+      "{size}x{size}, last {TREND_SOLVES}: {:.1}s avg vs {:.1}s before  {arrow} {word}",
+      trend.recent.as_secs_f64(),
+      trend.previous.as_secs_f64()
+    ))
+  }
+
+  /// Draw the list of puzzles solved this session over the board.
+  fn render_history(&self, selected: usize, area: Rect, buf: &mut Buffer) {
+    Clear.render(area, buf);
+    let block = Block::bordered()
+      .title(" Solved this session ")
+      .title_bottom(" Enter: play again ");
+    let inner = block.inner(area);
+    block.render(area, buf);
+    let [trend_area, inner] =
+      Layout::vertical([Constraint::Length(2), Constraint::Fill(1)]).areas(inner);
+    self.trend_line().render(trend_area, buf);
+    if self.solved.is_empty() {
+      Line::from("Nothing solved yet").render(inner, buf);
+      return;
+    }
+    // Scroll just far enough to keep the selection in view.
+    let skip = (selected + 1).saturating_sub(inner.height as usize);
+    for (i, solved) in self.solved.iter().enumerate().skip(skip) {
+      let y = inner.y + (i - skip) as u16;
+      if y >= inner.bottom() {
+        break;
+      }
+      let line = format!(
+        "{} {:>4} moves {:>6.1}s",
+        solved.code,
+        solved.moves,
+        solved.time.as_secs_f64()
+      );
+      let line = if i == selected {
+        Line::from(line).reversed()
+      } else {
+        Line::from(line)
+      };
+      line.render(
+        Rect {
+          y,
+          height: 1,
+          ..inner
+        },
+        buf,
+      );
+    }
+  }
+
+  /// What to say on the board once it's solved, cut short to fit in `width` columns.
+  /// Without colors, fancy text falls back to the plain default.
+  fn win_text(&self, width: usize) -> String {
+    let (text, ellipsis) = if self.color {
+      (self.theme.win_text.as_str(), "…")
+    } else if self.theme.win_text.is_ascii() {
+      (self.theme.win_text.as_str(), "...")
+    } else {
+      (DEFAULT_WIN_TEXT, "...")
+    };
+    if Span::raw(text).width() <= width {
+      return text.to_string();
+    }
+    let width = width.saturating_sub(Span::raw(ellipsis).width());
+    let end = text
+      .char_indices()
+      .map(|(i, c)| i + c.len_utf8())
+      .take_while(|&end| Span::raw(&text[..end]).width() <= width)
+      .last()
+      .unwrap_or(0);
+    format!("{}{ellipsis}", &text[..end])
+  }
+
+  /// Draw tile `n`, which belongs to the given cell, in the given area. The area is
+  /// usually the cell's own, but not while the tile is sliding.
+  fn render_tile(
+    &self,
+    n: u8,
+    (x, y): (usize, usize),
+    ghost: Option<u8>,
+    area: Rect,
+    buf: &mut Buffer,
+  ) {
+    let size = self.grid.size();
+    let locked = self.locked[y * size + x];
+    if self.theme.tile_style == TileStyle::Grid {
+      self.render_block_tile(n, (x, y), locked, ghost, area, buf);
+      return;
+    }
+    let mut block = Block::bordered()
+      .style(self.fg(self.theme.tile_color(&self.grid, n, (x, y))))
+      .border_set(self.theme.tile_border.set());
+    if locked {
+      // Locked tiles stand out with a different line from the rest.
+      let style = match self.theme.tile_border {
+        BorderStyle::Double => BorderStyle::Thick,
+        _ => BorderStyle::Double,
+      };
+      block = block.border_set(style.set());
+    }
+    if self.show_goals || self.peek.is_some() {
+      // Label the top border with the goal's row and column, so it stays out of the
+      // tile's inner area.
+      let (goal_x, goal_y) = self.grid.goal().position(size, n);
+      let label = format!("{},{}", goal_y + 1, goal_x + 1);
+      block = block.title_top(Line::from(label).dim().right_aligned());
+      if (x, y) == self.grid.goal().blank_position(size) {
+        // Mark the cell the blank has to end up in, too.
+        block = block.title_bottom(Line::from("◌").dim().right_aligned());
+      }
+    }
+    if let Some(ghost) = ghost {
+      // Keep the ghost on the border, out of the way of the tile's own number.
+      let label = self.theme.number(ghost);
+      block = block.title_bottom(Line::from(label).dim().left_aligned());
+    }
+    let text_area = block.inner(area);
+    block.render(area, buf);
+    if !self.hidden((x, y)) {
+      Line::from(self.theme.tile_label(n, text_area.width)).render(text_area, buf);
+    }
+  }
+
+  /// Draw a tile as a solid block, for the grid style. Without colors, the block is
+  /// reversed instead so it still stands out from the blank.
+  fn render_block_tile(
+    &self,
+    n: u8,
+    cell: (usize, usize),
+    locked: bool,
+    ghost: Option<u8>,
+    area: Rect,
+    buf: &mut Buffer,
+  ) {
+    let mut style = if self.color {
+      Style::default()
+        .fg(Color::Black)
+        .bg(self.theme.tile_color(&self.grid, n, cell))
+    } else {
+      Style::default().reversed()
+    };
+    if locked {
+      // There's no border to change, so mark the number instead.
+      style = style.underlined();
+    }
+    buf.set_style(area, style);
+    if !self.hidden(cell) {
+      Line::from(self.theme.tile_label(n, area.width)).render(middle_row(area), buf);
+    }
+    // A block has no border of its own to label, so labels go on the lines above and
+    // below it. Those are shared with the tiles above and below, so the goal goes at
+    // the left of the line above and the rest at the right of the line below, where
+    // they can't run into each other.
+    let above = area.y.checked_sub(1).map(|y| Rect {
+      y,
+      width: area.width.saturating_sub(1),
+      height: 1,
+      ..area
+    });
+    let below = Rect {
+      x: area.right().saturating_sub(1),
+      y: area.bottom(),
+      width: 2,
+      height: 1,
+    };
+    let size = self.grid.size();
+    if self.show_goals || self.peek.is_some() {
+      let (goal_x, goal_y) = self.grid.goal().position(size, n);
+      if let Some(above) = above {
+        let label = format!("{},{}", goal_y + 1, goal_x + 1);
+        Line::from(label)
+          .dim()
+          .render(above.intersection(buf.area), buf);
+      }
+      if cell == self.grid.goal().blank_position(size) {
+        // Mark the cell the blank has to end up in, too.
+        Line::from("◌")
+          .dim()
+          .render(below.intersection(buf.area), buf);
+      }
+    }
+    if let Some(ghost) = ghost {
+      // The blank's own cell has no ghost, so this never covers its mark.
+      let label = self.theme.number(ghost);
+      Line::from(label)
+        .dim()
+        .render(below.intersection(buf.area), buf);
+    }
+  }
+
+  /// Draw the lines between tiles, for the grid style.
+  fn render_grid_lines(&self, board_area: Rect, buf: &mut Buffer) {
+    let layout = self.layout;
+    let (columns, rows) = self.view_size();
+    let set = self.theme.tile_border.line_set();
+    let first = layout.tile(board_area, 0, 0);
+    let last = layout.tile(board_area, columns - 1, rows - 1);
+    let columns: Vec<u16> = (1..columns)
+      .map(|x| layout.tile(board_area, x, 0).x - 1)
+      .collect();
+    let rows: Vec<u16> = (1..rows)
+      .map(|y| layout.tile(board_area, 0, y).y - 1)
+      .collect();
+    for y in first.y..last.bottom() {
+      for x in first.x..last.right() {
+        let symbol = match (columns.contains(&x), rows.contains(&y)) {
+          (true, true) => set.cross,
+          (true, false) => set.vertical,
+          (false, true) => set.horizontal,
+          (false, false) => continue,
+        };
+        if let Some(cell) = buf.cell_mut((x, y)) {
+          cell.set_symbol(symbol);
+        }
+      }
+    }
+  }
+}
+
+/// The line halfway down an area, where a tile's number goes.
+fn middle_row(area: Rect) -> Rect {
+  Rect {
+    y: area.y + area.height / 2,
+    height: 1.min(area.height),
+    ..area
+  }
+}
+
+impl Widget for &App {
+  fn render(self, area: Rect, buf: &mut Buffer) {
+    let size = self.grid.size() as u16;
+    let layout = self.layout;
+    let area = self.padded(area);
+    let [
+      title_area,
+      instructions_area,
+      main_area,
+      gauge_area,
+      status_bar_area,
+      footer_area,
+    ] = self.areas(area);
+
+    let title = match self.grid.goal() {
+      Goal::Standard => "Sliding Puzzle".to_string(),
+      goal => format!("Sliding Puzzle ({} goal)", goal.name()),
+    };
+    let mut badge = self.difficulty.name().to_string();
+    if let Difficulty::Warmup(moves) = self.difficulty {
+      badge = format!("{badge} · solvable in ≤{moves} moves");
+    }
+    if let Some(moves) = self.start_moves {
+      let about = if self.grid.size() <= solver::MAX_OPTIMAL_SIZE {
+        ""
+      } else {
+        "~"
+      };
+      badge = format!("{badge} · {about}{moves} moves");
+    }
+    let badge = if self.color {
+      Span::from(format!(" {badge} "))
+        .fg(Color::Black)
+        .bg(self.difficulty.color())
+    } else {
+      Span::from(format!("[{badge}]"))
+    };
+    Line::from(vec![title.bold(), "  ".into(), badge]).render(title_area, buf);
+    let puzzle_area = self.board_area(main_area);
+    // Line the instructions up with the inside of the board, as far as they still fit.
+    let instructions = self.keys.instructions(self.movement);
+    let indent = (puzzle_area.x + layout.padding_x - area.x).min(
+      area
+        .width
+        .saturating_sub(instructions.chars().count() as u16),
+    );
+    Line::from(format!("{}{instructions}", " ".repeat(indent as usize)))
+      .render(instructions_area, buf);
+
+    let puzzle_border_color = if self.is_win() {
+      Color::Green
+    } else {
+      Color::Red
+    };
+
+    let mut puzzle_block = Block::bordered()
+      .border_style(self.fg(puzzle_border_color))
+      .border_set(self.theme.board_border.set());
+    if self.is_win() {
+      // Leave room for the corners and a space on either side.
+      let text = self.win_text(puzzle_area.width.saturating_sub(4) as usize);
+      puzzle_block = puzzle_block.title_bottom(Line::from(format!(" {text} ")).centered());
+    }
+    // Arrows on the border, where more of the board is scrolled out of sight.
+    let (columns, rows) = self.view_size();
+    let (scroll_x, scroll_y) = self.scroll();
+    let more = |arrow: &'static str| Line::from(arrow).style(self.fg(Color::Yellow));
+    if scroll_y > 0 {
+      puzzle_block = puzzle_block.title_top(more("↑").centered());
+    }
+    if scroll_y + rows < size as usize {
+      puzzle_block = puzzle_block.title_bottom(more("↓").centered());
+    }
+    let board_inside = puzzle_block.inner(puzzle_area);
+    puzzle_block.render(puzzle_area, buf);
+    let middle = puzzle_area.y + puzzle_area.height / 2;
+    if scroll_x > 0 {
+      more("←").render(
+        Rect::new(puzzle_area.x, middle, 1, 1).intersection(main_area),
+        buf,
+      );
+    }
+    if scroll_x + columns < size as usize {
+      let right = puzzle_area.right().saturating_sub(1);
+      more("→").render(Rect::new(right, middle, 1, 1).intersection(main_area), buf);
+    }
+    if let Some(color) = self.theme.board_background
+      && self.color
+    {
+      buf.set_style(board_inside, Style::default().bg(color));
+    }
+
+    if let Some((direction, _)) = self.flash {
+      // The edge on the side where the tile would have come from.
+      let edge = match direction.rotated(self.rotation) {
+        Direction::Up => Rect {
+          y: puzzle_area.bottom() - 1,
+          height: 1,
+          ..puzzle_area
+        },
+        Direction::Down => Rect {
+          height: 1,
+          ..puzzle_area
+        },
+        Direction::Left => Rect {
+          x: puzzle_area.right() - 1,
+          width: 1,
+          ..puzzle_area
+        },
+        Direction::Right => Rect {
+          width: 1,
+          ..puzzle_area
+        },
+      };
+      buf.set_style(
+        edge.intersection(main_area),
+        if self.color {
+          Style::default().fg(Color::LightRed)
+        } else {
+          Style::default().reversed()
+        },
+      );
+    }
+
+    if layout.gap > 0 {
+      self.render_grid_lines(puzzle_area, buf);
+    }
+    let placed = self.grid.placed();
+    // Where the blank will go if the player walks it to the cell they've picked.
+    let walk = self
+      .blank_target
+      .map(|target| self.blank_walk_cells(target))
+      .unwrap_or_default();
+    let mut sliding = Vec::new();
+    for (y, row) in self.grid.rows().enumerate() {
+      for (x, &number) in row.iter().enumerate() {
+        let Some(area) = self.tile_area(puzzle_area, (x, y)) else {
+          continue;
+        };
+        // The number that belongs here, unless it's already in place.
+        let ghost = self
+          .grid
+          .goal()
+          .target(self.grid.size(), x, y)
+          .filter(|_| self.show_ghosts && !placed[y * self.grid.size() + x]);
+        if let Some(n) = number {
+          match &self.slide_animation {
+            // Sliding tiles go on top of everything else, once the rest are drawn.
+            Some(animation) if animation.cells.contains(&(x, y)) => {
+              sliding.push((n, x, y, ghost));
+            }
+            _ => self.render_tile(n, (x, y), ghost, area, buf),
+          }
+        } else {
+          if let Some(color) = self.theme.blank
+            && self.color
+          {
+            buf.set_style(area, Style::default().bg(color));
+          }
+          if let Some(ghost) = ghost {
+            // Nothing covers the blank, so its ghost goes where a tile's number would.
+            let area = match self.theme.tile_style {
+              TileStyle::Bordered => Block::bordered().inner(area),
+              TileStyle::Grid => middle_row(area),
+            };
+            Line::from(self.theme.tile_label(ghost, area.width))
+              .dim()
+              .render(area, buf);
+          }
+        }
+        if let Some(trail) = &self.trail
+          && self.color
+          && let Some(&(_, left)) = trail.iter().find(|&&(cell, _)| cell == (x, y))
+        {
+          // Fade from a dim teal toward the terminal's own background.
+          let fade = 1.0 - left.elapsed().as_secs_f64() / TRAIL_FADE.as_secs_f64();
+          let fade = fade.clamp(0.0, 1.0);
+          let color = Color::Rgb(0, (110.0 * fade) as u8, (120.0 * fade) as u8);
+          buf.set_style(area, Style::default().bg(color));
+        }
+        if walk.contains(&(x, y)) {
+          let style = if self.color {
+            Style::default().bg(Color::DarkGray)
+          } else {
+            Style::default().underlined()
+          };
+          buf.set_style(area, style);
+        }
+        if self.blank_target == Some((x, y)) {
+          buf.set_style(area, Style::default().reversed());
+        }
+      }
+    }
+    if let Some(animation) = &self.slide_animation {
+      // Each tile starts a whole cell back from where it ends up, toward where it came
+      // from, and closes the distance.
+      let (dx, dy) = animation.direction.rotated(self.rotation).offset();
+      let behind = 1.0 - animation.progress();
+      for (n, x, y, ghost) in sliding {
+        let Some(area) = self.tile_area(puzzle_area, (x, y)) else {
+          continue;
+        };
+        let (view_x, view_y) = self.view_cell((x, y));
+        let came_into_view = view_x
+          .checked_add_signed(dx)
+          .zip(view_y.checked_add_signed(dy))
+          .is_none_or(|from| self.view_tile(puzzle_area, from).is_none());
+        // A tile coming from out of sight just shows up, rather than slide over the
+        // board's border.
+        let behind = if came_into_view { 0.0 } else { behind };
+        let shift = |cells: isize, pitch: u16| (cells as f64 * pitch as f64 * behind).round();
+        let area = Rect {
+          x: (area.x as f64 + shift(dx, layout.tile_width)) as u16,
+          y: (area.y as f64 + shift(dy, layout.tile_height)) as u16,
+          ..area
+        };
+        // Cover whatever was drawn in the cell it's passing over.
+        Clear.render(area, buf);
+        self.render_tile(n, (x, y), ghost, area, buf);
+      }
+    }
+
+    if self.show_lines {
+      // Check off finished lines to the right of the board where they run across it,
+      // and above it where they run down it. Turning the board swaps which is which.
+      let upright = self.rotation.is_multiple_of(2);
+      let (scroll_x, scroll_y) = self.scroll();
+      for i in 0..self.grid.size() {
+        let (x, y) = self.view_cell((i, i));
+        let lines = [
+          (self.grid.row_complete(i), upright),
+          (self.grid.column_complete(i), !upright),
+        ];
+        for (complete, across) in lines {
+          // Lines scrolled out of sight don't get a mark.
+          let tile = if across {
+            self.view_tile(puzzle_area, (scroll_x, y))
+          } else {
+            self.view_tile(puzzle_area, (x, scroll_y))
+          };
+          let Some(tile) = tile.filter(|_| complete) else {
+            continue;
+          };
+          let mark = if across {
+            Rect::new(puzzle_area.right() + 1, middle_row(tile).y, 1, 1)
+          } else {
+            Rect::new(
+              tile.x + tile.width / 2,
+              puzzle_area.y.saturating_sub(1),
+              1,
+              1,
+            )
+          };
+          Line::from("✓")
+            .style(self.fg(Color::Green))
+            .render(mark.intersection(main_area), buf);
+        }
+      }
+    }
+
+    let progress = self.progress();
+    let gauge_color = if self.is_win() {
+      Color::Green
+    } else {
+      // Fade from red to yellowish green, saving pure green for the win.
+      Color::Rgb(
+        (255.0 * (1.0 - progress)) as u8,
+        (200.0 * progress) as u8,
+        0,
+      )
+    };
+    Gauge::default()
+      .ratio(progress)
+      .label(format!("{:.0}%", progress * 100.0))
+      .gauge_style(self.fg(gauge_color))
+      .render(
+        Rect {
+          x: puzzle_area.x,
+          width: puzzle_area.width,
+          ..gauge_area
+        }
+        .intersection(gauge_area),
+        buf,
+      );
+
+    let time = self.elapsed().as_secs();
+    // Moves that can be taken back right now, as far as the undo limit allows.
+    let undos = self
+      .undos_remaining()
+      .map_or(self.history.len(), |remaining| {
+        self.history.len().min(remaining as usize)
+      });
+    let mut fields = vec![
+      Field::new("Moves", self.score().to_string(), 11),
+      Field::new("Time", format!("{}:{:02}", time / 60, time % 60), 11),
+      Field::new("Solved", format!("{:.0}%", progress * 100.0), 12),
+      Field::new("Undo", format!("↶{undos}"), 10).dimmed(undos == 0),
+      Field::new("Difficulty", self.difficulty.name().to_string(), 18),
+    ];
+    // Only while the player could be moving: once it's solved, or while the board moves
+    // by itself, there's nothing to wait for.
+    let by_itself = self.attract.is_some() || self.spectate.is_some() || self.showcase.is_some();
+    if self.show_idle && !self.is_win() && !by_itself {
+      let idle = self.last_move.elapsed().as_secs();
+      let value = if idle < 60 {
+        format!("{idle}s")
+      } else {
+        format!("{}:{:02}", idle / 60, idle % 60)
+      };
+      // Turned on to be watched, so it goes ahead of the undos and difficulty, which are
+      // dropped first when there isn't room.
+      fields.insert(3, Field::new("Idle", value, 10).dimmed(idle < IDLE_NOTICE));
+    }
+    if self.show_inversions {
+      fields.push(Field::new(
+        "Inversions",
+        self.grid.inversions().to_string(),
+        15,
+      ));
+    }
+    StatusBar { fields }.render(
+      Rect {
+        x: puzzle_area.x,
+        width: status_bar_area.right().saturating_sub(puzzle_area.x),
+        ..status_bar_area
+      }
+      .intersection(status_bar_area),
+      buf,
+    );
+
+    let status_area = Rect {
+      x: puzzle_area.x,
+      y: footer_area.y,
+      width: footer_area.right().saturating_sub(puzzle_area.x),
+      height: 1,
+    };
+    let mut status = if let Some(attract) = &self.attract {
+      if attract.saved.is_some() {
+        vec!["Press any key to get back to your game".to_string()]
+      } else {
+        vec!["Press any key to play".to_string()]
+      }
+    } else if let Some(spectate) = &self.spectate {
+      vec![format!(
+        "Spectating {}: press any key to take over",
+        spectate.path.display()
+      )]
+    } else if let Some(showcase) = &self.showcase {
+      vec![showcase.status()]
+    } else {
+      Vec::new()
+    };
+    let clock = |time: Duration| format!("{}:{:02}", time.as_secs() / 60, time.as_secs() % 60);
+    if let Some(tournament) = &self.tournament {
+      match tournament.result {
+        None => status.push(format!(
+          "{}  Total: {} moves, {}",
+          match tournament.kind {
+            TournamentKind::Seeds { .. } => {
+              format!("Puzzle {} of {}", tournament.current + 1, tournament.count)
+            }
+            TournamentKind::Sizes => format!(
+              "Level: {0}x{0} ({1} of {2})",
+              self.grid.size(),
+              tournament.current + 1,
+              tournament.count
+            ),
+            TournamentKind::Daily { date, .. } => format!("Daily puzzle for {date}"),
+          },
+          tournament.moves + self.score(),
+          clock(
+            tournament
+              .started
+              .elapsed()
+              .saturating_sub(self.pause_so_far())
+          )
+        )),
+        Some(result) => {
+          status.push(format!(
+            "{} over: {} moves in {}, score {}",
+            match tournament.kind {
+              TournamentKind::Seeds { .. } => "Tournament",
+              TournamentKind::Sizes => "Campaign",
+              TournamentKind::Daily { .. } => "Daily puzzle",
+            },
+            result.moves,
+            clock(result.time),
+            result.score()
+          ));
+          if let Some(best) = tournament
+            .best
+            .get(&tournament.name(self.grid.goal(), self.difficulty))
+          {
+            status.push(format!("Best score: {}", best.score()));
+          }
+          if let Some(rank) = tournament.daily_rank(self.grid.goal(), self.difficulty) {
+            status.push(rank);
+          }
+        }
+      }
+      if let (TournamentKind::Daily { .. }, None) = (tournament.kind, tournament.result) {
+        // Going back to a day shows how it went before, if it's been played.
+        let name = tournament.name(self.grid.goal(), self.difficulty);
+        status.push(match tournament.best.get(&name) {
+          Some(best) => format!(
+            "Best on this day: {} moves in {}",
+            best.moves,
+            clock(best.time)
+          ),
+          None => "First try at this day's puzzle".to_string(),
+        });
+      }
+    }
+    if self.is_win() && self.moves != self.key_presses {
+      // Scores differ depending on how moves are counted, so show both.
+      status.push(format!(
+        "Slides: {}  Key presses: {}",
+        self.moves, self.key_presses
+      ));
+    }
+    if self.track_optimal && self.attract.is_none() && self.showcase.is_none() {
+      status.push(match self.solutions.get(&self.grid) {
+        Some(path) => format!("Optimal moves left: {}", path.len()),
+        None => "Optimal moves left: working it out...".to_string(),
+      });
+    }
+    if let Some(best) = self.best.get(&self.record_key()) {
+      status.push(format!("Your best: {best} moves"));
+    }
+    if let Some(best) = self
+      .reference
+      .as_ref()
+      .and_then(|reference| reference.get(&self.record_key()))
+    {
+      status.push(format!("Shared best: {best} moves"));
+    }
+    if let Some(remaining) = self.undos_remaining() {
+      status.push(format!("Undos left: {remaining}"));
+    }
+    if self.is_win() && self.clean_solve() {
+      status.push("Clean solve!".to_string());
+    } else if self.is_win() && self.studied {
+      status.push("Studied, so it's not recorded".to_string());
+    } else if self.is_win() && self.assisted {
+      status.push("Solved with a row assist".to_string());
+    } else if self.studying {
+      let key = self.keys.study.to_ascii_uppercase();
+      match self.solutions.get(&self.grid).map(Vec::as_slice) {
+        // Named the way the board's shown, so it matches the key that would make it.
+        Some(path @ [next, ..]) => status.push(format!(
+          "{key}: {} ({} left)",
+          self.movement.describe(next.rotated(self.rotation)),
+          path.len()
+        )),
+        Some([]) => {}
+        None => status.push(format!("{key}: working out the solution...")),
+      }
+    } else if self.row_assist
+      && let Some(y) = self.assist_row()
+    {
+      status.push(format!(
+        "{}: finish row {}",
+        self.keys.finish_row.to_ascii_uppercase(),
+        y + 1
+      ));
+    }
+    if self.is_win() && self.moves > 0 {
+      status.push(self.direction_counts());
+      if !self.summary {
+        status.push("Enter: summary".to_string());
+      }
+    }
+    if let Some(advice) = self.advice {
+      let how = if advice == self.difficulty.harder() {
+        "a breeze"
+      } else {
+        "a struggle"
+      };
+      status.push(format!(
+        "{} looks like {how} lately; try --difficulty {}",
+        self.difficulty.name(),
+        advice.name().to_lowercase()
+      ));
+    }
+    if self.escape_pause {
+      status.push("Paused: press any other key to carry on".to_string());
+    } else if self.paused.is_some() {
+      status.push("Paused while the terminal is in the background".to_string());
+    } else if !self.paused_time.is_zero() {
+      status.push(format!("Time paused: {}", clock(self.paused_time)));
+    }
+    if let Some(message) = &self.message {
+      status.push(message.clone());
+    }
+    let mut status = Line::from(status.join("  "));
+    if self.coach_note.is_some() {
+      status.push_span("  ↑ further from solved".dim());
+    }
+    status.render(status_area.intersection(footer_area), buf);
+
+    if self.ghost
+      && let Some(run) = self.runs.get(&self.record_key())
+    {
+      // Beside the board, if there's room: three columns a tile, as the grid prints,
+      // with a space inside the border on either side.
+      let ghost_area = Rect {
+        x: puzzle_area.right() + 1,
+        y: puzzle_area.y,
+        width: 3 * size + 3,
+        height: size + 2,
+      };
+      if main_area.union(ghost_area) == main_area {
+        self.render_ghost(run, ghost_area, buf);
+      }
+    }
+    if let Some(browser) = &self.browser {
+      self.render_browser(browser, puzzle_area.intersection(main_area), buf);
+    }
+    if let Some(selected) = self.history_view {
+      // Wider than the board, so long position codes fit.
+      let list_area = Rect {
+        width: main_area.right().saturating_sub(puzzle_area.x),
+        ..puzzle_area
+      };
+      self.render_history(selected, list_area.intersection(main_area), buf);
+    }
+    if self.summary {
+      self.render_summary(puzzle_area.intersection(main_area), buf);
+    }
+    if self.sharing {
+      // Everything below the instructions, since a QR code needs more room than the
+      // board does.
+      let share_area = Rect {
+        height: area.bottom() - main_area.y,
+        ..main_area
+      };
+      self.render_share(share_area, buf);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::time::Instant;
+
+  use crate::app::tests::app;
+  use crate::demo_grid;
+  use crate::grid::Grid;
+  use crate::layout::LayoutConfig;
+
+  #[test]
+  fn two_by_two_boards_draw_in_any_space() {
+    let app = app(Grid::from_code("1302").unwrap());
+    for (width, height) in [(80, 24), (30, 12), (12, 6), (4, 2), (1, 1), (0, 0)] {
+      let area = Rect::new(0, 0, width, height);
+      let mut buf = Buffer::empty(area);
+      (&app).render(area, &mut buf);
+    }
+    let area = Rect::new(0, 0, 80, 24);
+    let mut buf = Buffer::empty(area);
+    (&app).render(area, &mut buf);
+    let text: String = buf.content().iter().map(|cell| cell.symbol()).collect();
+    for tile in ["01", "02", "03"] {
+      assert!(text.contains(tile), "tile {tile} isn't drawn:\n{text}");
+    }
+  }
+
+  /// The rows of text the game draws, in a terminal of the given size.
+  fn rows(app: &App, width: u16, height: u16) -> Vec<String> {
+    let area = Rect::new(0, 0, width, height);
+    let mut buf = Buffer::empty(area);
+    app.render(area, &mut buf);
+    (0..height)
+      .map(|y| (0..width).map(|x| buf[(x, y)].symbol()).collect())
+      .collect()
+  }
+
+  #[test]
+  fn grid_tiles_show_goals_and_ghosts() {
+    let mut app = app(demo_grid());
+    app.theme.tile_style = TileStyle::Grid;
+    app.layout = LayoutConfig::for_size(4, TileStyle::Grid);
+    app.show_ghosts = true;
+    app.peek = Some(Instant::now() + Duration::from_secs(60));
+    let peeking = rows(&app, 60, 20);
+    let text = peeking.join("\n");
+    // Goals on the line above each tile.
+    assert!(text.contains("┏1,1━━1,2━━1,3━━1,4━┓"), "{text}");
+    // Ghosts on the line below, and the mark for where the blank goes.
+    assert!(text.contains("┗━━━13━━━14━━━━━━━━◌┛"), "{text}");
+
+    app.peek = None;
+    app.show_goals = true;
+    assert_eq!(rows(&app, 60, 20), peeking);
+  }
+}
@@ -0,0 +1,139 @@
+use std::time::{Duration, Instant};
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::widgets::Widget;
+
+use crate::app::App;
+use crate::cli::{flag_value, size_value};
+use crate::config::{Defaults, Theme};
+use crate::error::Error;
+use crate::grid::{Goal, Grid};
+use crate::keys::{KeyBindings, Preset};
+use crate::stats::MoveMetric;
+use crate::{Align, Difficulty, Options, new_grid_for, solver};
+
+/// The screen the renderer draws to, in columns and rows: a roomy terminal window.
+const SCREEN: Rect = Rect {
+  x: 0,
+  y: 0,
+  width: 120,
+  height: 40,
+};
+
+/// Settings for the bench subcommand.
+pub struct BenchOptions {
+  /// How many puzzles to solve, or frames to draw. Each has its own default.
+  count: Option<u32>,
+  size: usize,
+  seed: u64,
+  /// The longest any one puzzle, or the average frame, may take before the run counts
+  /// as a failure.
+  limit: Option<Duration>,
+  /// Whether to time drawing the board instead of the solver.
+  render: bool,
+}
+
+/// Parse the arguments that follow "bench" on the command line.
+pub fn parse_args(mut args: impl Iterator<Item = String>) -> Result<BenchOptions, String> {
+  let mut options = BenchOptions {
+    count: None,
+    size: 4,
+    seed: 0,
+    limit: None,
+    render: false,
+  };
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "--count" => options.count = Some(flag_value(&mut args, &arg)?),
+      "--size" => options.size = size_value(&mut args, &arg)?,
+      "--seed" => options.seed = flag_value(&mut args, &arg)?,
+      "--limit" => options.limit = Some(Duration::from_secs_f64(flag_value(&mut args, &arg)?)),
+      "--render" => options.render = true,
+      _ => return Err(format!("Unrecognized argument to bench: {arg:?}")),
+    }
+  }
+  Ok(options)
+}
+
+/// Time the solver on a fixed set of seeded puzzles, printing how long each one took.
+/// Exits with an error if any puzzle took longer than the limit, so a slower solver
+/// gets noticed.
+pub fn run(options: BenchOptions) -> Result<(), Error> {
+  if options.render {
+    return render(&options);
+  }
+  let count = options.count.unwrap_or(10);
+  let limit = options.limit.unwrap_or(Duration::from_secs(30));
+  // The first solve sets up tables that every later solve shares, so time that apart.
+  let start = Instant::now();
+  solver::solve(&Grid::solved(options.size, Goal::Standard));
+  println!("setup: {:.2?}", start.elapsed());
+
+  let mut rng = StdRng::seed_from_u64(options.seed);
+  let mut slowest = Duration::ZERO;
+  for _ in 0..count {
+    let grid = new_grid_for(options.size, &Goal::Standard, Difficulty::Hard, &mut rng);
+    let start = Instant::now();
+    let moves = solver::solve(&grid).map_or(0, |solution| solution.len());
+    let elapsed = start.elapsed();
+    println!("{}: {moves} moves in {elapsed:.2?}", grid.code());
+    slowest = slowest.max(elapsed);
+  }
+
+  if slowest > limit {
+    return Err(Error::Failed(format!(
+      "the slowest puzzle took {slowest:.2?}, over the limit of {limit:.2?}"
+    )));
+  }
+  Ok(())
+}
+
+/// Time drawing a seeded puzzle to a screen in memory, first on its own and then with
+/// every overlay turned on, printing the average time per frame for each. No terminal
+/// is needed. Exits with an error if either average is over the limit, if one was given.
+fn render(options: &BenchOptions) -> Result<(), Error> {
+  let count = options.count.unwrap_or(1000).max(1);
+  let defaults = Defaults {
+    size: options.size,
+    difficulty: Difficulty::Hard,
+    keys: Preset::Standard,
+    align: Align::Center,
+  };
+  let mut rng = StdRng::seed_from_u64(options.seed);
+  let grid = new_grid_for(options.size, &Goal::Standard, Difficulty::Hard, &mut rng);
+  let mut app = App::new(
+    Options::new(&defaults),
+    grid,
+    KeyBindings::default(),
+    Theme::default(),
+    MoveMetric::default(),
+    None,
+    None,
+  );
+  let mut slowest = Duration::ZERO;
+  for overlays in [false, true] {
+    app.show_overlays(overlays);
+    let mut buf = Buffer::empty(SCREEN);
+    let start = Instant::now();
+    for _ in 0..count {
+      buf.reset();
+      (&app).render(SCREEN, &mut buf);
+    }
+    let frame = start.elapsed() / count;
+    let name = if overlays { "overlays" } else { "plain" };
+    println!("{name}: {count} frames, {frame:.2?} per frame");
+    slowest = slowest.max(frame);
+  }
+
+  if let Some(limit) = options.limit
+    && slowest > limit
+  {
+    return Err(Error::Failed(format!(
+      "the slowest frame took {slowest:.2?} on average, over the limit of {limit:.2?}"
+    )));
+  }
+  Ok(())
+}
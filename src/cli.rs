@@ -0,0 +1,405 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::config::Defaults;
+use crate::date::Date;
+use crate::grid::{Direction, MAX_SIZE, MIN_SIZE};
+use crate::keys::{ENV_VARS, Preset};
+use crate::notation::parse_moves;
+use crate::settings::Settings;
+use crate::{
+  CAMPAIGN_START, Command, Difficulty, Mode, Options, REWIND_MOVES, bench, generate, solver,
+};
+
+/// Parse the command line. --help prints usage and exits.
+pub fn parse_args(defaults: &Defaults) -> Result<Command, String> {
+  let mut args = std::env::args().skip(1).peekable();
+  if args.peek().is_some_and(|arg| arg == "generate") {
+    args.next();
+    return generate::parse_args(args).map(Command::Generate);
+  }
+  if args.peek().is_some_and(|arg| arg == "bench") {
+    args.next();
+    return bench::parse_args(args).map(Command::Bench);
+  }
+
+  let mut options = Options::new(defaults);
+  // Start from what the player used last time, where it still makes sense.
+  let settings = Settings::load();
+  if let Some(size) = settings
+    .size
+    .filter(|size| (MIN_SIZE..=MAX_SIZE).contains(size))
+  {
+    options.size = size;
+  }
+  if let Some(difficulty) = settings.difficulty.as_deref().and_then(Difficulty::from_id) {
+    options.difficulty = difficulty;
+  }
+  if let Some(keys) = settings.keys.and_then(|keys| keys.parse().ok()) {
+    options.keys = keys;
+  }
+  if let Some(align) = settings.align.and_then(|align| align.parse().ok()) {
+    options.align = align;
+  }
+  options.theme = settings.theme;
+  let mut color = None;
+
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "-h" | "--help" => {
+        print_help();
+        std::process::exit(0);
+      }
+      "--demo" => options.mode = Mode::Demo,
+      "--demo-static" => options.mode = Mode::DemoStatic,
+      "--attract" => options.mode = Mode::Attract,
+      "--tournament" => match flag_value(&mut args, &arg)? {
+        0 => return Err("--tournament needs at least one puzzle".to_string()),
+        count => options.mode = Mode::Tournament(count),
+      },
+      "--daily" => {
+        let date: Date = flag_value(&mut args, &arg)?;
+        if date > Date::today() {
+          return Err(format!("There's no daily puzzle for {date} yet"));
+        }
+        options.mode = Mode::Daily(date);
+      }
+      "--campaign" => match size_value(&mut args, &arg)? {
+        size if size < CAMPAIGN_START => {
+          return Err(format!(
+            "{arg} should be from {CAMPAIGN_START} to {MAX_SIZE}"
+          ));
+        }
+        size => options.mode = Mode::Campaign(size),
+      },
+      "--seed" => options.seed = flag_value(&mut args, &arg)?,
+      "--size" => {
+        options.size = size_value(&mut args, &arg)?;
+        options.size_given = true;
+      }
+      "--difficulty" => options.difficulty = flag_value(&mut args, &arg)?,
+      "--warmup" => match flag_value(&mut args, &arg)? {
+        0 => return Err("--warmup needs at least one move".to_string()),
+        moves => options.difficulty = Difficulty::Warmup(moves),
+      },
+      "--undos" => options.undo_limit = Some(flag_value(&mut args, &arg)?),
+      "--flash" => options.flash_illegal_moves = true,
+      "--coach" => options.coach = true,
+      "--ghost" => options.ghost = true,
+      "--optimal" => options.track_optimal = true,
+      "--auto-lock" => options.auto_lock = true,
+      "--row-assist" => options.row_assist = true,
+      "--no-advice" => options.advise = false,
+      "--no-animation" => options.animate = false,
+      "--padding" => options.padding = Some(flag_value(&mut args, &arg)?),
+      "--tile-colors" => options.color_scheme = Some(flag_value(&mut args, &arg)?),
+      "--win-screen" => options.win_screen = Some(flag_value(&mut args, &arg)?),
+      "--color" => color = Some(true),
+      "--no-color" => color = Some(false),
+      "--keys" => options.keys = flag_value(&mut args, &arg)?,
+      "--align" => options.align = flag_value(&mut args, &arg)?,
+      "--verbose" => options.verbose = true,
+      "--no-title" => options.set_title = false,
+      "--no-focus-pause" => options.focus_pause = false,
+      "--reveal-on-hover" => options.reveal_on_hover = true,
+      "--moves" => options.moves = moves_value(&mut args, &arg)?,
+      "--import" => options.import = Some(flag_value(&mut args, &arg)?),
+      "--goal-file" => options.goal_file = Some(flag_value(&mut args, &arg)?),
+      "--exit-after" => options.exit_after = true,
+      "--print-config" => options.print_config = true,
+      "--snapshot" => {
+        let path: PathBuf = flag_value(&mut args, &arg)?;
+        if path.extension().is_none_or(|extension| extension != "svg") {
+          return Err("--snapshot can only save .svg files".to_string());
+        }
+        options.snapshot = Some(path);
+      }
+      "--check" => options.check = Some(flag_value(&mut args, &arg)?),
+      "--with-length" => options.with_length = true,
+      "--within" => options.within = Some(flag_value(&mut args, &arg)?),
+      "--repair" => options.repair = true,
+      "--stats-reset" => options.stats_reset = true,
+      "--yes" => options.yes = true,
+      "--reference-scores" => options.reference_scores = Some(flag_value(&mut args, &arg)?),
+      "--spectate" => options.spectate = Some(flag_value(&mut args, &arg)?),
+      _ => return Err(format!("Unrecognized argument {arg:?}")),
+    }
+  }
+  (options.color, options.color_reason) = match color {
+    Some(color) => (color, "chosen on the command line".to_string()),
+    None => detect_color(),
+  };
+  Ok(Command::Play(Box::new(options)))
+}
+
+/// Work out whether the terminal can show colors, and why we think so. Terminals that
+/// can't would show the escape codes as garbage.
+pub fn detect_color() -> (bool, String) {
+  // See https://no-color.org
+  if std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+    return (false, "NO_COLOR is set".to_string());
+  }
+  match std::env::var("TERM") {
+    Ok(term) if term.is_empty() || term == "dumb" => {
+      (false, format!("TERM={term:?} has no colors"))
+    }
+    Ok(term) => (
+      true,
+      format!(
+        "TERM={term:?}, about {} colors",
+        crossterm::style::available_color_count()
+      ),
+    ),
+    // The Windows console has colors but doesn't set TERM.
+    Err(_) if cfg!(windows) => (true, "Windows console".to_string()),
+    Err(_) => (false, "TERM isn't set".to_string()),
+  }
+}
+
+/// Take the scripted moves that follow a flag on the command line, one letter per move.
+pub fn moves_value(
+  args: &mut impl Iterator<Item = String>,
+  flag: &str,
+) -> Result<Vec<Direction>, String> {
+  let value = args.next().ok_or_else(|| format!("{flag} needs a value"))?;
+  parse_moves(&value)
+    .map_err(|letter| format!("Unknown move {letter:?} in {flag} (expected u, d, l or r)"))
+}
+
+/// Take the board size that follows a flag on the command line, checking that it's
+/// one we support.
+pub fn size_value(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<usize, String> {
+  let size = flag_value(args, flag)?;
+  if !(MIN_SIZE..=MAX_SIZE).contains(&size) {
+    return Err(format!("{flag} should be from {MIN_SIZE} to {MAX_SIZE}"));
+  }
+  Ok(size)
+}
+
+/// Take the value that follows a flag on the command line and parse it.
+pub fn flag_value<T: FromStr>(
+  args: &mut impl Iterator<Item = String>,
+  flag: &str,
+) -> Result<T, String> {
+  let value = args.next().ok_or_else(|| format!("{flag} needs a value"))?;
+  value
+    .parse()
+    .map_err(|_| format!("Invalid value for {flag}: {value:?}"))
+}
+
+pub fn print_help() {
+  println!("Sliding puzzle in Ratatui");
+  println!();
+  println!("Usage: slyde [--demo | --demo-static | --attract | --tournament N [--seed N]");
+  println!("             | --campaign N | --daily DATE]");
+  println!("             [--size N] [--difficulty LEVEL | --warmup N] [--undos N] [--flash]");
+  println!("             [--coach] [--optimal] [--auto-lock] [--row-assist] [--no-advice]");
+  println!("             [--no-animation] [--padding N] [--tile-colors SCHEME] [--ghost]");
+  println!("             [--win-screen board|summary]");
+  println!("             [--color | --no-color] [--keys PRESET] [--verbose] [--no-title]");
+  println!("             [--align left|center] [--no-focus-pause] [--reveal-on-hover]");
+  println!("             [--moves MOVES | --import FILE] [--exit-after | --snapshot FILE]");
+  println!("             [--repair] [--goal-file FILE] [--print-config]");
+  println!("             [--reference-scores FILE] [--spectate FILE]");
+  println!("       slyde --check CODE [--with-length | --within N] [--repair]");
+  println!("       slyde --stats-reset [--yes]");
+  println!("       slyde generate [--count N] [--size N] [--difficulty LEVEL] [--seed N]");
+  println!("                      [--format code|grid] [--output FILE]");
+  println!("       slyde bench [--render] [--count N] [--size N] [--seed N]");
+  println!("                   [--limit SECONDS]");
+  println!();
+  println!("Options:");
+  println!("  --demo              Solve a fixed board by itself, then scramble and solve it");
+  println!("                      again, the same way every time, until a key is pressed.");
+  println!("                      + and - change the speed and space pauses, here and in");
+  println!("                      a showcase");
+  println!("  --demo-static       Start from the demo's fixed, nearly solved board");
+  println!("  --attract           Make random moves on their own until a key is pressed");
+  println!("  --tournament N      Solve N seeded puzzles in a row, for a combined score of");
+  println!("                      one point per move and one per second. R starts the");
+  println!("                      current puzzle over, and once it's over, starts the");
+  println!("                      whole tournament again");
+  println!("  --seed N            With --tournament, start from seed N (default 0)");
+  println!(
+    "  --campaign N        Solve one puzzle of each size from {CAMPAIGN_START}x{CAMPAIGN_START} up to NxN, scored"
+  );
+  println!("                      like a tournament");
+  println!("  --daily DATE        Solve the puzzle for a date like 2024-01-31, or today. It's");
+  println!("                      the same for everyone on the same size and difficulty.");
+  println!("                      Your best on each day is kept, and each solve is ranked");
+  println!("                      against your other days that month. Dates are in UTC");
+  println!(
+    "  --size N            Play on an N by N board, from {MIN_SIZE} to {MAX_SIZE} (default 4)"
+  );
+  println!("  --difficulty LEVEL  How well to shuffle: easy, medium, or hard (default)");
+  println!("                      In game, Shift+R reshuffles one level harder");
+  println!("  --warmup N          Shuffle with just N random moves from solved, so it never");
+  println!("                      takes more than N to solve. Good for getting started");
+  println!("  --undos N           Only allow N undos per puzzle");
+  println!("                      In game, B takes back {REWIND_MOVES} moves as one undo,");
+  println!("                      C sets a checkpoint and Shift+C goes back to it");
+  println!("  --flash             Flash the edge of the board when a move can't be made");
+  println!("  --coach             Point out moves that take tiles further from where they go");
+  println!("  --ghost             Race your best run on a position you've solved before,");
+  println!("                      replayed at its own pace on a faint board beside yours");
+  println!("  --optimal           Count down the fewest moves left, and say when you stray");
+  println!("  --auto-lock         Lock each tile as soon as it's in place, as if by pressing K");
+  println!("  --row-assist        Let F make the last few moves of a row that's one tile");
+  println!("                      short. A solve with its help isn't a clean solve");
+  println!("  --no-advice         Don't suggest an easier or harder difficulty after a win");
+  println!("  --no-animation      Move whole rows and columns at once, instead of smoothly");
+  println!("  --padding N         Leave N blank cells around the whole game, when there's");
+  println!("                      room for them");
+  println!("  --tile-colors SCHEME");
+  println!("                      Color tiles by number (odd-even, the default), by which");
+  println!("                      quarter of the board they go in (region), or by how far");
+  println!("                      they are from it (heat)");
+  println!("  --win-screen WHAT   On a win, keep the solved board in view (board, the");
+  println!("                      default) or show a summary of the solve over it");
+  println!("                      (summary)");
+  println!("  --color             Use colors even if the terminal doesn't seem to have them");
+  println!("  --no-color          Don't use colors (also set by the NO_COLOR variable)");
+  println!("  --keys PRESET       Start from a set of key bindings: standard (default) or");
+  println!("                      one-handed (see below)");
+  println!("  --align left|center Keep the board near the left edge (default), or center it");
+  println!("  --no-title          Don't show the game's status in the terminal's title");
+  println!("  --no-focus-pause    Keep the clock running while the terminal is in the");
+  println!("                      background");
+  println!("  --reveal-on-hover   Hide the numbers on the tiles, except the one under the");
+  println!("                      mouse, to play from memory");
+  println!("  --verbose           Print details about the setup, like whether colors are on");
+  println!("  --moves MOVES       Slide tiles before play starts, one letter per move:");
+  println!("                      u, d, l or r for the direction the tile slides");
+  println!("  --import FILE       Play back a move list saved with X, from its start");
+  println!("                      position. - reads the list from stdin. If the moves go");
+  println!("                      wrong partway, like in a file cut off while saving, the");
+  println!("                      ones before are played");
+  println!("  --repair            With --import or --check, swap the two highest tiles of a");
+  println!("                      start position that can't be solved, so it can be,");
+  println!("                      instead of stopping with an error");
+  println!("  --goal-file FILE    Solve toward your own arrangement: a position code, like");
+  println!("                      the ones X saves, with the blank (0) last. It sets the");
+  println!("                      board's size. - reads it from stdin");
+  println!("  --exit-after        With --moves or --import, print the board afterwards and");
+  println!("                      exit");
+  println!("  --print-config      Print the settings in effect, from the config file, the");
+  println!("                      last game, the environment and these flags, as a config");
+  println!("                      file, and exit");
+  println!("  --snapshot FILE     Save a picture of the board as an SVG file, after any");
+  println!("                      --moves or --import, in the current theme, and exit");
+  println!("  --check CODE        Print whether the position is solvable and exit, with");
+  println!("                      status 1 if it isn't. - reads the code from stdin");
+  println!("  --with-length       With --check, also print how many moves it takes (the");
+  println!(
+    "                      fewest up to {0}x{0})",
+    solver::MAX_OPTIMAL_SIZE
+  );
+  println!("  --within N          With --check, fail unless it can be solved in N moves");
+  println!("                      or fewer, and print the length of a solution that fits.");
+  println!("                      It needn't be the shortest, so this can be quicker");
+  println!(
+    "                      than --with-length. Past {0}x{0}, only a quick solution",
+    solver::MAX_OPTIMAL_SIZE
+  );
+  println!("                      is tried, so a failure there isn't certain");
+  println!("  --stats-reset       Delete your best scores, recent times and saved settings,");
+  println!("                      after asking first unless --yes is given. Saved move");
+  println!("                      lists are kept");
+  println!("  --reference-scores FILE");
+  println!("                      Show the best moves from someone else's scores file");
+  println!("                      (a copy of best_moves.txt) next to your own");
+  println!("  --spectate FILE     Play the moves another program writes to FILE, or to a");
+  println!("                      named pipe, as they come, in the same letters as --moves.");
+  println!("                      Waits for FILE to exist, and any key takes over");
+  println!("  -h, --help          Print this help and exit");
+  println!();
+  println!("The size, difficulty, key preset, alignment and theme are remembered from the");
+  println!("last game, in settings.toml in the data directory. Flags override them.");
+  println!();
+  println!("Generate options:");
+  println!("  --count N           How many puzzles to print (default 1)");
+  println!("  --seed N            Seed the shuffle, to get the same puzzles every time");
+  println!("  --format FORMAT     code (default) for one position code per line,");
+  println!("                      or grid to draw each puzzle as rows of numbers");
+  println!("  --output FILE       Write to FILE instead of standard output");
+  println!();
+  println!("Bench options, for timing the solver on the same puzzles every run:");
+  println!("  --render            Time drawing the board instead, to a 120x40 screen in");
+  println!("                      memory, plain and with every overlay on");
+  println!("  --count N           How many puzzles to solve (default 10), or frames to");
+  println!("                      draw (default 1000)");
+  println!("  --seed N            Which puzzles to solve (default 0)");
+  println!("  --limit SECONDS     Fail if any puzzle takes longer than this (default 30),");
+  println!("                      or with --render, the average frame");
+  println!();
+  println!("More keys:");
+  println!("  W/A/S/D, arrows     Slide a tile the way the key points, or move the blank");
+  println!("                      that way with movement = \"blank\" in the config file.");
+  println!("                      The line under the title says \"Move blank\" when it's");
+  println!("                      set. --moves and saved moves always name the way the");
+  println!("                      tile slides");
+  println!("  Shift+W/A/S/D       Slide every tile between the blank and the edge");
+  println!("  Shift+arrows        Look around a board too big for the terminal, which");
+  println!("                      otherwise scrolls to keep the blank in sight");
+  println!("  K                   Lock the tiles that are in place, so they can't be moved by");
+  println!("                      mistake. Locking too early can leave no way to finish,");
+  println!("                      so Shift+K unlocks them all");
+  println!("  Shift+G             Show the goal labels only while held");
+  println!("  N                   Study the solution: the first press shows the solver's");
+  println!("                      next move, and each press after makes it. Studied");
+  println!("                      puzzles aren't recorded");
+  println!("  L                   Check off the rows and columns that are finished");
+  println!("  E                   Show faintly which number belongs in each cell");
+  println!("  T                   Leave a fading trail where the blank has been");
+  println!("  I                   Count the pairs of tiles that are in the wrong order.");
+  println!("                      Moving up or down changes the count by the number of");
+  println!("                      tiles passed over, which is why only half of all");
+  println!("                      boards can be solved");
+  println!("  V                   Switch to the next theme, which is kept for next time");
+  println!("  ?                   Hide the line of keys under the title, giving the board");
+  println!("                      its room, or show it again");
+  println!("  .                   Show how long it's been since your last move, to spot");
+  println!("                      where you hesitate");
+  println!("  Z                   Turn the board a quarter turn clockwise, and Shift+Z");
+  println!("                      turns it back. Only the view turns: the goal is still");
+  println!("                      the right way up, and the keys move tiles the way");
+  println!("                      they point on screen");
+  println!("  Y                   List the puzzles solved this session, to play one again");
+  println!("                      and show whether your times on this size are improving");
+  println!("  O                   Look through seeded puzzles, and how hard each one is");
+  println!("  X                   Save the start position and moves to a file in the data");
+  println!("                      directory. Each move is U, D, L or R for the direction the");
+  println!("                      tile slides; the blank goes the opposite way");
+  println!("  M                   Pick a cell with the movement keys, then press Enter to");
+  println!("                      walk the blank there. The cells it will pass through are");
+  println!("                      highlighted as you pick");
+  println!("  J                   Show the position's code, as a QR code in builds with");
+  println!("                      the qr feature, to photograph and play somewhere else");
+  println!("  Enter               On a solved board, show a summary of the solve");
+  println!("  Esc                 Close anything showing over the board. On the board,");
+  println!("                      stop the clock until the next key, or with escape in");
+  println!("                      the config file set to quit or nothing, do that instead");
+  println!();
+  println!("The one-handed preset keeps everything within reach of the right hand:");
+  let one_handed = Preset::OneHanded.bindings();
+  for (key, action) in one_handed.letters() {
+    println!("  {:<20}{}", key.to_ascii_uppercase(), action.name());
+  }
+  println!("  Shift+I/J/K/L       Slide every tile between the blank and the edge");
+  println!("  Shift+O, Shift+P    Restart one level harder, go back to the checkpoint");
+  println!("  Shift+M             Show the goal labels only while held");
+  println!();
+  println!("Environment variables (each set to a single character), which override the");
+  println!("preset:");
+  for (name, _) in ENV_VARS {
+    println!("  {name}");
+  }
+  println!();
+  println!("Exit codes:");
+  println!("  0                   Success");
+  println!("  1                   The board wasn't solved after --moves with --exit-after,");
+  println!("                      bench went over its limit, or --check found the");
+  println!("                      position unsolvable");
+  println!("  2                   Bad command line, or a move in --moves can't be made");
+  println!("  3                   Couldn't read or write a file or the terminal");
+}
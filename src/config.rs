@@ -0,0 +1,882 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use ratatui::style::Color;
+use ratatui::symbols::{border, line};
+use serde::{Deserialize, Serialize};
+
+use crate::grid::{Direction, Goal, Grid, MAX_SIZE, MIN_SIZE};
+use crate::keys::Preset;
+use crate::stats::MoveMetric;
+use crate::{Align, Difficulty};
+
+/// Settings read from the config file. Anything missing from the file gets its default.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+  /// The board size to start with, unless the last game's settings or the command line
+  /// say otherwise.
+  pub size: Option<usize>,
+  /// The difficulty to start with, like "hard" or "warmup10", in the same way.
+  pub difficulty: Option<String>,
+  /// The key preset to start from, "standard" or "one-handed", in the same way.
+  pub key_preset: Option<String>,
+  /// Where to put the board, "left" or "center", in the same way.
+  pub align: Option<String>,
+  /// Keys for specific actions, over the preset. Each is named after its SLYDE_*_KEY
+  /// environment variable, in lowercase without SLYDE_ and _KEY, like "quit" or
+  /// "finish_row", and set to a single character. The environment variables win.
+  pub keys: BTreeMap<String, String>,
+  /// The built-in themes the theme key cycles through, in order: any of "classic",
+  /// "palette", "forest" and "mono". All of them by default. The settings below apply
+  /// on top of whichever one is showing.
+  pub themes: Option<Vec<String>>,
+  /// Colors for specific tiles, overriding the usual odd/even colors. Keys are tile
+  /// numbers and values are anything ratatui can parse as a color, like "yellow" or
+  /// "#ffd700".
+  pub tile_colors: BTreeMap<String, String>,
+  /// Give every tile a color of its own from a fixed palette, instead of alternating
+  /// blue and gray. Each number always gets the same color.
+  pub random_tile_colors: bool,
+  /// How to color the rest of the tiles: "odd-even" (the default) to alternate by
+  /// number, "region" by which quarter of the board each one belongs in, or "heat" by
+  /// how far each one is from where it belongs.
+  pub tile_color_scheme: Option<String>,
+  /// A color to fill the blank square with, so it stands out from the background.
+  pub blank_color: Option<String>,
+  /// A color to fill the inside of the board with, so it stands out from the terminal.
+  pub board_background: Option<String>,
+  /// The style of the line around the board: "thick" (the default), "double",
+  /// "rounded" or "plain".
+  pub board_border: Option<String>,
+  /// The style of the line around each tile, from the same choices. "plain" is the
+  /// default.
+  pub tile_border: Option<String>,
+  /// How to draw the tiles: "bordered" (the default) for a box around each one, or
+  /// "grid" for plain colored blocks with lines between them.
+  pub tile_style: Option<String>,
+  /// Where each tile's number sits across the tile: "left", "center" (the default) or
+  /// "right".
+  pub number_align: Option<String>,
+  /// Write tile numbers as they are, like 7, instead of padding them with a zero to two
+  /// digits, like 07.
+  pub unpadded_numbers: bool,
+  /// What to say on the board when it's solved, like "🎉 Nailed it!". Anything that
+  /// isn't plain ASCII is only used with colors on, since a terminal without colors
+  /// probably can't show it either.
+  pub win_text: Option<String>,
+  /// What counts as a move for scoring: "slides" (each tile) or "keys" (each key press).
+  pub move_metric: Option<String>,
+  /// The fewest moves a hard puzzle's solution may take. Hard shuffles that come out
+  /// easier than this are reshuffled.
+  pub min_hard_moves: Option<usize>,
+  /// Which arrangement counts as solved: "standard" (counting up) or "reverse"
+  /// (counting down).
+  pub goal: Option<String>,
+  /// A file holding a goal of your own, as a position code with the blank (0) last,
+  /// used instead of goal. It only fits the size of board it's written for.
+  pub goal_file: Option<String>,
+  /// When to ask before quitting: "unsolved" (the default) only while a puzzle is under
+  /// way and not yet solved, "always", or "never".
+  pub confirm_quit: Option<String>,
+  /// What Escape does on the board: "pause" (the default) stops the clock until the
+  /// next key, "quit" works like the quit key, and "nothing" leaves it alone. Whatever
+  /// this is, Escape closes anything showing over the board.
+  pub escape: Option<String>,
+  /// What to show once the puzzle's solved: "board" (the default) keeps the solved
+  /// board in view until Enter brings up the summary, and "summary" shows it right away.
+  pub win_screen: Option<String>,
+  /// What the movement keys move: "tiles" (the default) slides a tile the way the key
+  /// points, and "blank" moves the blank that way instead. Saved moves always name the
+  /// way the tile slid, whichever this is.
+  pub movement: Option<String>,
+  /// Start with the line of key instructions under the title hidden, giving its room
+  /// to the board. The instructions key shows it again.
+  pub hide_instructions: bool,
+  /// Start with the time since the last move showing in the status bar. The idle key
+  /// turns it on and off.
+  pub show_idle: bool,
+  /// How many blank cells to leave around the whole game, on every side. It's dropped
+  /// when the terminal is too small to fit the board with it.
+  pub padding: Option<u16>,
+  /// After this many seconds without a key press, let the board move by itself like a
+  /// screensaver until the next key. Off unless set.
+  pub idle_attract_seconds: Option<u64>,
+  /// The most moves a second the board makes when it moves by itself: in attract mode,
+  /// showcases and the demo, and when spectating. Slower terminals, or ones over SSH,
+  /// can flicker at full speed. No cap unless set.
+  pub max_moves_per_second: Option<f64>,
+}
+
+/// The colors for random_tile_colors. Each is light enough for black numbers on top of
+/// it, as in the grid style, and strong enough to read on a dark background.
+const TILE_PALETTE: [Color; 8] = [
+  Color::Rgb(230, 100, 90),
+  Color::Rgb(240, 160, 60),
+  Color::Rgb(220, 200, 70),
+  Color::Rgb(110, 190, 90),
+  Color::Rgb(70, 180, 170),
+  Color::Rgb(90, 150, 230),
+  Color::Rgb(160, 120, 220),
+  Color::Rgb(220, 110, 180),
+];
+
+/// The colors for the region scheme, for the top left, top right, bottom left and
+/// bottom right quarters of the board. Light enough for black numbers, like the palette.
+const REGION_COLORS: [Color; 4] = [
+  Color::Rgb(90, 150, 230),
+  Color::Rgb(110, 190, 90),
+  Color::Rgb(240, 160, 60),
+  Color::Rgb(160, 120, 220),
+];
+
+/// The colors for the heat scheme, from a tile in place to one at least
+/// `HEAT_COLORS.len() - 1` moves away.
+const HEAT_COLORS: [Color; 4] = [
+  Color::Rgb(110, 190, 90),
+  Color::Rgb(220, 200, 70),
+  Color::Rgb(240, 160, 60),
+  Color::Rgb(230, 100, 90),
+];
+
+/// Where play starts, before the last game's settings and the command line have their
+/// say.
+pub struct Defaults {
+  pub size: usize,
+  pub difficulty: Difficulty,
+  pub keys: Preset,
+  pub align: Align,
+}
+
+/// What the board says when it's solved, unless the config says something else.
+pub const DEFAULT_WIN_TEXT: &str = "Solved!";
+
+/// The colors and lines to draw the board with, checked and parsed from the config.
+pub struct Theme {
+  /// The name of the built-in theme this one starts from.
+  pub name: &'static str,
+  /// The colors for tiles with odd and even numbers, unless they have one of their own.
+  pub odd: Color,
+  pub even: Color,
+  /// Colors for specific tiles, by tile number.
+  pub tile_colors: HashMap<u8, Color>,
+  /// Whether tiles without a color of their own get one from the palette.
+  pub random_tile_colors: bool,
+  pub color_scheme: TileColorScheme,
+  /// The fill for the blank square, if it has one.
+  pub blank: Option<Color>,
+  /// The fill for the inside of the board, if it has one.
+  pub board_background: Option<Color>,
+  pub board_border: BorderStyle,
+  /// The line around each tile, or between tiles in the grid style.
+  pub tile_border: BorderStyle,
+  pub tile_style: TileStyle,
+  pub number_align: NumberAlign,
+  /// Whether numbers under 10 get a leading zero.
+  pub zero_pad: bool,
+  pub win_text: String,
+}
+
+impl Theme {
+  /// The color tile `n` is drawn in, when it's in the cell at (x, y) of the grid.
+  pub fn tile_color(&self, grid: &Grid, n: u8, (x, y): (usize, usize)) -> Color {
+    match self.tile_colors.get(&n) {
+      Some(&color) => color,
+      None if self.random_tile_colors => {
+        // Scatter the numbers over the palette with a multiplicative hash, so neighbors
+        // rarely match but each number always gets the same color.
+        let hash = u32::from(n).wrapping_mul(2_654_435_761);
+        TILE_PALETTE[(hash >> 29) as usize]
+      }
+      None => match self.color_scheme {
+        TileColorScheme::OddEven if n.is_multiple_of(2) => self.even,
+        TileColorScheme::OddEven => self.odd,
+        TileColorScheme::Region => {
+          let size = grid.size();
+          let (goal_x, goal_y) = grid.goal().position(size, n);
+          REGION_COLORS[goal_y * 2 / size * 2 + goal_x * 2 / size]
+        }
+        TileColorScheme::DistanceHeat => {
+          HEAT_COLORS[grid.distance(x, y).min(HEAT_COLORS.len() - 1)]
+        }
+      },
+    }
+  }
+}
+
+impl Theme {
+  /// Tile `n`'s number, with spaces on either side to fill the given width, so it sits
+  /// where the theme puts numbers on a tile that wide.
+  pub fn tile_label(&self, n: u8, width: u16) -> String {
+    let number = self.number(n);
+    let width = usize::from(width);
+    // Keep numbers a column off the tile's edges if there's room beside two digits.
+    let margin = usize::from(width >= 4);
+    let space = width.saturating_sub(number.len() + 2 * margin);
+    let left = margin
+      + match self.number_align {
+        NumberAlign::Left => 0,
+        // Any odd space goes on the left, which puts two digits on a tile of three
+        // columns against its right edge, as they always were.
+        NumberAlign::Center => space.div_ceil(2),
+        NumberAlign::Right => space,
+      };
+    let right = width.saturating_sub(left + number.len());
+    format!("{}{number}{}", " ".repeat(left), " ".repeat(right))
+  }
+
+  /// Tile `n`'s number on its own, padded with a zero if the theme asks for it.
+  pub fn number(&self, n: u8) -> String {
+    if self.zero_pad {
+      format!("{n:02}")
+    } else {
+      n.to_string()
+    }
+  }
+}
+
+impl Default for Theme {
+  fn default() -> Self {
+    BaseTheme::Classic.theme()
+  }
+}
+
+/// A theme built into the game, for the config file's settings to go on top of.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BaseTheme {
+  Classic,
+  /// A color of its own for every tile.
+  Palette,
+  Forest,
+  /// White tiles, for terminals where the other colors are hard to read.
+  Mono,
+}
+
+impl BaseTheme {
+  pub const ALL: [BaseTheme; 4] = [
+    BaseTheme::Classic,
+    BaseTheme::Palette,
+    BaseTheme::Forest,
+    BaseTheme::Mono,
+  ];
+
+  pub fn name(self) -> &'static str {
+    match self {
+      BaseTheme::Classic => "classic",
+      BaseTheme::Palette => "palette",
+      BaseTheme::Forest => "forest",
+      BaseTheme::Mono => "mono",
+    }
+  }
+
+  /// The theme as it is, before the config changes anything.
+  pub fn theme(self) -> Theme {
+    let classic = Theme {
+      name: self.name(),
+      odd: Color::Blue,
+      even: Color::Gray,
+      tile_colors: HashMap::new(),
+      random_tile_colors: false,
+      color_scheme: TileColorScheme::OddEven,
+      blank: None,
+      board_background: None,
+      board_border: BorderStyle::Thick,
+      tile_border: BorderStyle::Plain,
+      tile_style: TileStyle::Bordered,
+      number_align: NumberAlign::Center,
+      zero_pad: true,
+      win_text: DEFAULT_WIN_TEXT.to_string(),
+    };
+    match self {
+      BaseTheme::Classic => classic,
+      BaseTheme::Palette => Theme {
+        random_tile_colors: true,
+        board_border: BorderStyle::Rounded,
+        tile_border: BorderStyle::Rounded,
+        ..classic
+      },
+      BaseTheme::Forest => Theme {
+        odd: Color::Green,
+        even: Color::LightYellow,
+        board_border: BorderStyle::Double,
+        ..classic
+      },
+      BaseTheme::Mono => Theme {
+        odd: Color::White,
+        even: Color::White,
+        board_border: BorderStyle::Plain,
+        ..classic
+      },
+    }
+  }
+}
+
+impl FromStr for BaseTheme {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    BaseTheme::ALL
+      .into_iter()
+      .find(|theme| theme.name() == s)
+      .ok_or_else(|| format!("unknown theme {s:?} (expected classic, palette, forest or mono)"))
+  }
+}
+
+/// A style of line to draw a border with. Every style is one cell wide, so the layout
+/// doesn't depend on which is picked.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BorderStyle {
+  Thick,
+  Double,
+  Rounded,
+  Plain,
+}
+
+impl BorderStyle {
+  /// The characters ratatui draws this style with.
+  pub fn set(self) -> border::Set {
+    match self {
+      BorderStyle::Thick => border::THICK,
+      BorderStyle::Double => border::DOUBLE,
+      BorderStyle::Rounded => border::ROUNDED,
+      BorderStyle::Plain => border::PLAIN,
+    }
+  }
+
+  /// The characters for lines in this style that cross each other, as in the grid
+  /// between tiles.
+  pub fn line_set(self) -> line::Set {
+    match self {
+      BorderStyle::Thick => line::THICK,
+      BorderStyle::Double => line::DOUBLE,
+      BorderStyle::Rounded => line::ROUNDED,
+      BorderStyle::Plain => line::NORMAL,
+    }
+  }
+}
+
+impl FromStr for BorderStyle {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "thick" => Ok(BorderStyle::Thick),
+      "double" => Ok(BorderStyle::Double),
+      "rounded" => Ok(BorderStyle::Rounded),
+      "plain" => Ok(BorderStyle::Plain),
+      _ => Err(format!(
+        "unknown border style {s:?} (expected thick, double, rounded or plain)"
+      )),
+    }
+  }
+}
+
+/// How tiles get their colors, unless they have one of their own.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum TileColorScheme {
+  /// The theme's odd and even colors, by the tile's number.
+  #[default]
+  OddEven,
+  /// A color for each quarter of the board, by where the tile belongs.
+  Region,
+  /// From green for a tile in place to red for one far from it.
+  DistanceHeat,
+}
+
+impl TileColorScheme {
+  pub fn name(self) -> &'static str {
+    match self {
+      TileColorScheme::OddEven => "odd-even",
+      TileColorScheme::Region => "region",
+      TileColorScheme::DistanceHeat => "heat",
+    }
+  }
+}
+
+impl FromStr for TileColorScheme {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    [
+      TileColorScheme::OddEven,
+      TileColorScheme::Region,
+      TileColorScheme::DistanceHeat,
+    ]
+    .into_iter()
+    .find(|scheme| scheme.name() == s)
+    .ok_or_else(|| format!("unknown color scheme {s:?} (expected odd-even, region or heat)"))
+  }
+}
+
+/// How tiles are drawn.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TileStyle {
+  /// Each tile has a box of its own around it.
+  Bordered,
+  /// Tiles are colored blocks, with single lines shared between neighbors. This fits
+  /// more rows in the same height.
+  Grid,
+}
+
+impl FromStr for TileStyle {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "bordered" => Ok(TileStyle::Bordered),
+      "grid" => Ok(TileStyle::Grid),
+      _ => Err(format!(
+        "unknown tile style {s:?} (expected bordered or grid)"
+      )),
+    }
+  }
+}
+
+/// Where a tile's number sits across the tile.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum NumberAlign {
+  Left,
+  #[default]
+  Center,
+  Right,
+}
+
+impl FromStr for NumberAlign {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "left" => Ok(NumberAlign::Left),
+      "center" => Ok(NumberAlign::Center),
+      "right" => Ok(NumberAlign::Right),
+      _ => Err(format!(
+        "unknown number alignment {s:?} (expected left, center or right)"
+      )),
+    }
+  }
+}
+
+/// When quitting needs the quit key pressed twice.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ConfirmQuit {
+  Always,
+  /// Only when moves have been made on a puzzle that isn't solved yet, so there's
+  /// something to lose.
+  #[default]
+  Unsolved,
+  Never,
+}
+
+impl ConfirmQuit {
+  pub fn name(self) -> &'static str {
+    match self {
+      ConfirmQuit::Always => "always",
+      ConfirmQuit::Unsolved => "unsolved",
+      ConfirmQuit::Never => "never",
+    }
+  }
+}
+
+impl FromStr for ConfirmQuit {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    [
+      ConfirmQuit::Always,
+      ConfirmQuit::Unsolved,
+      ConfirmQuit::Never,
+    ]
+    .into_iter()
+    .find(|confirm| confirm.name() == s)
+    .ok_or_else(|| format!("unknown choice {s:?} (expected always, unsolved or never)"))
+  }
+}
+
+/// What the Escape key does while playing.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Escape {
+  /// Stop the clock until the next key.
+  #[default]
+  Pause,
+  /// The same as the quit key, asking first if the quit key would.
+  Quit,
+  Nothing,
+}
+
+impl Escape {
+  pub const ALL: [Escape; 3] = [Escape::Pause, Escape::Quit, Escape::Nothing];
+
+  pub fn name(self) -> &'static str {
+    match self {
+      Escape::Pause => "pause",
+      Escape::Quit => "quit",
+      Escape::Nothing => "nothing",
+    }
+  }
+}
+
+impl FromStr for Escape {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Escape::ALL
+      .into_iter()
+      .find(|escape| escape.name() == s)
+      .ok_or_else(|| format!("unknown choice {s:?} (expected pause, quit or nothing)"))
+  }
+}
+
+/// What takes over the screen once the puzzle is solved.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum WinScreen {
+  /// Leave the solved board in view, with the summary a key press away.
+  #[default]
+  Board,
+  /// Show the summary over the board straight away.
+  Summary,
+}
+
+impl WinScreen {
+  pub const ALL: [WinScreen; 2] = [WinScreen::Board, WinScreen::Summary];
+
+  pub fn name(self) -> &'static str {
+    match self {
+      WinScreen::Board => "board",
+      WinScreen::Summary => "summary",
+    }
+  }
+}
+
+impl FromStr for WinScreen {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    WinScreen::ALL
+      .into_iter()
+      .find(|screen| screen.name() == s)
+      .ok_or_else(|| format!("unknown win screen {s:?} (expected board or summary)"))
+  }
+}
+
+/// Which piece the movement keys move. A key moving the blank up slides the tile above
+/// it down, so the two ways of playing press opposite keys for the same move.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Movement {
+  #[default]
+  Tiles,
+  Blank,
+}
+
+impl Movement {
+  pub const ALL: [Movement; 2] = [Movement::Tiles, Movement::Blank];
+
+  pub fn name(self) -> &'static str {
+    match self {
+      Movement::Tiles => "tiles",
+      Movement::Blank => "blank",
+    }
+  }
+
+  /// The move a movement key pointing the given way makes.
+  pub fn key_move(self, direction: Direction) -> Direction {
+    match self {
+      Movement::Tiles => direction,
+      Movement::Blank => direction.opposite(),
+    }
+  }
+
+  /// A move, described the way the player makes it.
+  pub fn describe(self, direction: Direction) -> String {
+    match self {
+      Movement::Tiles => format!("slide {}", direction.name()),
+      Movement::Blank => format!("move the blank {}", direction.opposite().name()),
+    }
+  }
+}
+
+impl FromStr for Movement {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Movement::ALL
+      .into_iter()
+      .find(|movement| movement.name() == s)
+      .ok_or_else(|| format!("unknown movement {s:?} (expected tiles or blank)"))
+  }
+}
+
+/// The config file's location, following the XDG convention.
+pub fn config_path() -> Option<PathBuf> {
+  if let Some(dir) = std::env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+    return Some(PathBuf::from(dir).join("slyde/config.toml"));
+  }
+  std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config/slyde/config.toml"))
+}
+
+impl Config {
+  /// Load the config file. It's fine for the file not to exist. If it can't be parsed,
+  /// fall back to the defaults. Returns the config along with warnings about anything
+  /// that couldn't be used.
+  pub fn load() -> (Self, Vec<String>) {
+    let Some(path) = config_path() else {
+      return (Config::default(), Vec::new());
+    };
+    let Ok(contents) = fs::read_to_string(&path) else {
+      return (Config::default(), Vec::new());
+    };
+    match toml::from_str(&contents) {
+      Ok(config) => (config, Vec::new()),
+      Err(err) => (
+        Config::default(),
+        vec![format!("Ignoring {}: {}", path.display(), err.message())],
+      ),
+    }
+  }
+
+  /// Where play starts, along with warnings about anything that had to fall back to
+  /// the game's own defaults.
+  pub fn defaults(&self) -> (Defaults, Vec<String>) {
+    let mut defaults = Defaults {
+      size: 4,
+      difficulty: Difficulty::Hard,
+      keys: Preset::Standard,
+      align: Align::Left,
+    };
+    let mut warnings = Vec::new();
+    match self.size {
+      None => {}
+      Some(size) if (MIN_SIZE..=MAX_SIZE).contains(&size) => defaults.size = size,
+      Some(size) => warnings.push(format!(
+        "Ignoring size: {size} isn't from {MIN_SIZE} to {MAX_SIZE}"
+      )),
+    }
+    if let Some(difficulty) = &self.difficulty {
+      match Difficulty::from_id(difficulty) {
+        Some(difficulty) => defaults.difficulty = difficulty,
+        None => warnings.push(format!(
+          "Ignoring difficulty: unknown difficulty {difficulty:?} (expected easy, medium, \
+           hard or warmupN)"
+        )),
+      }
+    }
+    if let Some(preset) = &self.key_preset {
+      match preset.parse() {
+        Ok(preset) => defaults.keys = preset,
+        Err(err) => warnings.push(format!("Ignoring key_preset: {err}")),
+      }
+    }
+    if let Some(align) = &self.align {
+      match align.parse() {
+        Ok(align) => defaults.align = align,
+        Err(()) => warnings.push(format!(
+          "Ignoring align: unknown alignment {align:?} (expected left or center)"
+        )),
+      }
+    }
+    (defaults, warnings)
+  }
+
+  /// The themes to cycle through, each with the config's settings on top, along with
+  /// warnings about anything that was skipped. There's always at least one.
+  pub fn themes(&self) -> (Vec<Theme>, Vec<String>) {
+    let mut bases = Vec::new();
+    let mut warnings = Vec::new();
+    for name in self.themes.iter().flatten() {
+      match name.parse() {
+        Ok(base) if !bases.contains(&base) => bases.push(base),
+        Ok(_) => {}
+        Err(err) => warnings.push(format!("Ignoring themes entry: {err}")),
+      }
+    }
+    if bases.is_empty() {
+      if self.themes.is_some() {
+        warnings.push("Ignoring themes: no themes left to use".to_string());
+      }
+      bases = BaseTheme::ALL.to_vec();
+    }
+    let mut themes = Vec::new();
+    for base in bases {
+      // Every theme gets the same settings, so the warnings only need saying once.
+      let (theme, theme_warnings) = self.theme(base);
+      if themes.is_empty() {
+        warnings.extend(theme_warnings);
+      }
+      themes.push(theme);
+    }
+    (themes, warnings)
+  }
+
+  /// The validated colors on top of a built-in theme, along with warnings about any
+  /// entries that were skipped.
+  fn theme(&self, base: BaseTheme) -> (Theme, Vec<String>) {
+    let mut theme = base.theme();
+    theme.random_tile_colors |= self.random_tile_colors;
+    let mut warnings = Vec::new();
+    for (tile, color) in &self.tile_colors {
+      let Some(n) = tile
+        .parse()
+        .ok()
+        .filter(|&n| n >= 1 && usize::from(n) < MAX_SIZE * MAX_SIZE)
+      else {
+        warnings.push(format!("Ignoring tile_colors.{tile}: not a tile number"));
+        continue;
+      };
+      match color.parse() {
+        Ok(color) => {
+          theme.tile_colors.insert(n, color);
+        }
+        Err(_) => warnings.push(format!(
+          "Ignoring tile_colors.{tile}: unknown color {color:?}"
+        )),
+      }
+    }
+    for (name, value, field) in [
+      ("blank_color", &self.blank_color, &mut theme.blank),
+      (
+        "board_background",
+        &self.board_background,
+        &mut theme.board_background,
+      ),
+    ] {
+      let Some(color) = value else {
+        continue;
+      };
+      match color.parse() {
+        Ok(color) => *field = Some(color),
+        Err(_) => warnings.push(format!("Ignoring {name}: unknown color {color:?}")),
+      }
+    }
+    for (name, value, field) in [
+      ("board_border", &self.board_border, &mut theme.board_border),
+      ("tile_border", &self.tile_border, &mut theme.tile_border),
+    ] {
+      let Some(style) = value else {
+        continue;
+      };
+      match style.parse() {
+        Ok(style) => *field = style,
+        Err(err) => warnings.push(format!("Ignoring {name}: {err}")),
+      }
+    }
+    if let Some(scheme) = &self.tile_color_scheme {
+      match scheme.parse() {
+        Ok(scheme) => theme.color_scheme = scheme,
+        Err(err) => warnings.push(format!("Ignoring tile_color_scheme: {err}")),
+      }
+    }
+    if let Some(style) = &self.tile_style {
+      match style.parse() {
+        Ok(style) => theme.tile_style = style,
+        Err(err) => warnings.push(format!("Ignoring tile_style: {err}")),
+      }
+    }
+    if let Some(align) = &self.number_align {
+      match align.parse() {
+        Ok(align) => theme.number_align = align,
+        Err(err) => warnings.push(format!("Ignoring number_align: {err}")),
+      }
+    }
+    theme.zero_pad &= !self.unpadded_numbers;
+    match self.win_text.as_deref().map(str::trim) {
+      None => {}
+      Some("") => warnings.push("Ignoring win_text: it's empty".to_string()),
+      Some(text) if text.chars().any(char::is_control) => {
+        warnings.push("Ignoring win_text: it has to fit on one line".to_string())
+      }
+      Some(text) => theme.win_text = text.to_string(),
+    }
+    (theme, warnings)
+  }
+
+  /// The validated move metric, along with a warning if it had to fall back to the default.
+  pub fn move_metric(&self) -> (MoveMetric, Vec<String>) {
+    match self.move_metric.as_deref().map(str::parse).transpose() {
+      Ok(metric) => (metric.unwrap_or_default(), Vec::new()),
+      Err(err) => (
+        MoveMetric::default(),
+        vec![format!("Ignoring move_metric: {err}")],
+      ),
+    }
+  }
+
+  /// How long to wait without input before attract mode starts, if it should, along with
+  /// a warning if the setting can't be used.
+  pub fn idle_attract(&self) -> (Option<Duration>, Vec<String>) {
+    match self.idle_attract_seconds {
+      Some(0) => (
+        None,
+        vec!["Ignoring idle_attract_seconds: it has to be more than 0".to_string()],
+      ),
+      seconds => (seconds.map(Duration::from_secs), Vec::new()),
+    }
+  }
+
+  /// The shortest time between the board's own moves, from max_moves_per_second, along
+  /// with a warning if it couldn't be used.
+  pub fn move_cap(&self) -> (Option<Duration>, Vec<String>) {
+    match self.max_moves_per_second {
+      Some(rate) if rate <= 0.0 || !rate.is_finite() => (
+        None,
+        vec!["Ignoring max_moves_per_second: it has to be more than 0".to_string()],
+      ),
+      rate => (
+        rate.map(|rate| Duration::from_secs_f64(1.0 / rate)),
+        Vec::new(),
+      ),
+    }
+  }
+
+  /// When to confirm quitting, along with a warning if it had to fall back to the
+  /// default.
+  pub fn confirm_quit(&self) -> (ConfirmQuit, Vec<String>) {
+    match self.confirm_quit.as_deref().map(str::parse).transpose() {
+      Ok(confirm) => (confirm.unwrap_or_default(), Vec::new()),
+      Err(err) => (
+        ConfirmQuit::default(),
+        vec![format!("Ignoring confirm_quit: {err}")],
+      ),
+    }
+  }
+
+  /// What Escape does, along with a warning if it had to fall back to the default.
+  pub fn escape(&self) -> (Escape, Vec<String>) {
+    match self.escape.as_deref().map(str::parse).transpose() {
+      Ok(escape) => (escape.unwrap_or_default(), Vec::new()),
+      Err(err) => (Escape::default(), vec![format!("Ignoring escape: {err}")]),
+    }
+  }
+
+  /// What to show on a win, along with a warning if it had to fall back to the default.
+  pub fn win_screen(&self) -> (WinScreen, Vec<String>) {
+    match self.win_screen.as_deref().map(str::parse).transpose() {
+      Ok(screen) => (screen.unwrap_or_default(), Vec::new()),
+      Err(err) => (
+        WinScreen::default(),
+        vec![format!("Ignoring win_screen: {err}")],
+      ),
+    }
+  }
+
+  /// What the movement keys move, along with a warning if it had to fall back to the
+  /// default.
+  pub fn movement(&self) -> (Movement, Vec<String>) {
+    match self.movement.as_deref().map(str::parse).transpose() {
+      Ok(movement) => (movement.unwrap_or_default(), Vec::new()),
+      Err(err) => (
+        Movement::default(),
+        vec![format!("Ignoring movement: {err}")],
+      ),
+    }
+  }
+
+  /// The validated goal, along with a warning if it had to fall back to the default.
+  pub fn goal(&self) -> (Goal, Vec<String>) {
+    match self.goal.as_deref().map(str::parse).transpose() {
+      Ok(goal) => (goal.unwrap_or_default(), Vec::new()),
+      Err(err) => (Goal::default(), vec![format!("Ignoring goal: {err}")]),
+    }
+  }
+}
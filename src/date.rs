@@ -0,0 +1,97 @@
+use std::fmt;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A day on the calendar, for the daily puzzle. Days start and end at midnight UTC,
+/// so everyone has the same puzzle at the same time.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct Date {
+  year: i32,
+  month: u32,
+  day: u32,
+}
+
+impl Date {
+  /// The date now, in UTC.
+  pub fn today() -> Date {
+    let seconds = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map_or(0, |since| since.as_secs());
+    Date::from_days(seconds / 86_400)
+  }
+
+  /// The date the given number of days after 1970-01-01. See
+  /// https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+  fn from_days(days: u64) -> Date {
+    let days = days as i64 + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+      (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    // Counting from March, so the leap day comes last.
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+      shifted_month + 3
+    } else {
+      shifted_month - 9
+    } as u32;
+    let year = (year_of_era + era * 400) as i32 + i32::from(month <= 2);
+    Date { year, month, day }
+  }
+
+  /// The seed for this date's puzzle, which reads like the date itself: 20240131 for
+  /// 2024-01-31.
+  pub fn seed(self) -> u64 {
+    self.year as u64 * 10_000 + u64::from(self.month) * 100 + u64::from(self.day)
+  }
+
+  /// Every day of the same month, from the first.
+  pub fn month_days(self) -> impl Iterator<Item = Date> {
+    (1..=days_in_month(self.year, self.month)).map(move |day| Date { day, ..self })
+  }
+
+  /// The month, written like "2024-01".
+  pub fn month_name(self) -> String {
+    format!("{}-{:02}", self.year, self.month)
+  }
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+  match month {
+    2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+    2 => 28,
+    4 | 6 | 9 | 11 => 30,
+    _ => 31,
+  }
+}
+
+/// Written like "2024-01-31".
+impl fmt::Display for Date {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}-{:02}-{:02}", self.year, self.month, self.day)
+  }
+}
+
+/// Reads a date written like "2024-01-31", or "today".
+impl FromStr for Date {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    if s == "today" {
+      return Ok(Date::today());
+    }
+    let bad = || format!("{s:?} isn't a date like 2024-01-31");
+    let mut fields = s.splitn(3, '-');
+    let mut field = || fields.next().and_then(|field| field.parse().ok());
+    let (Some(year), Some(month), Some(day)) = (field(), field(), field()) else {
+      return Err(bad());
+    };
+    let year = i32::try_from(year).map_err(|_| bad())?;
+    if !(1..=12).contains(&month) || !(1..=days_in_month(year, month)).contains(&day) {
+      return Err(bad());
+    }
+    Ok(Date { year, month, day })
+  }
+}
@@ -0,0 +1,45 @@
+use std::fmt;
+use std::io;
+use std::process::ExitCode;
+
+/// Ways a run can fail. Each has its own exit code, so scripts can tell them apart.
+#[derive(Debug)]
+pub enum Error {
+  /// The run finished, but not the way it was meant to, like a scripted board that
+  /// isn't solved.
+  Failed(String),
+  /// Like `Failed`, but what went wrong has already been printed as the run's output,
+  /// like --check's "unsolvable", so there's nothing more to say.
+  Quiet,
+  /// The command line didn't make sense.
+  Usage(String),
+  /// Reading or writing something went wrong.
+  Io(io::Error),
+}
+
+impl Error {
+  /// The process exit code for this error. Success is 0.
+  pub fn exit_code(&self) -> ExitCode {
+    match self {
+      Error::Failed(_) | Error::Quiet => ExitCode::from(1),
+      Error::Usage(_) => ExitCode::from(2),
+      Error::Io(_) => ExitCode::from(3),
+    }
+  }
+}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Error::Failed(message) | Error::Usage(message) => write!(f, "{message}"),
+      Error::Quiet => Ok(()),
+      Error::Io(err) => write!(f, "{err}"),
+    }
+  }
+}
+
+impl From<io::Error> for Error {
+  fn from(err: io::Error) -> Self {
+    Error::Io(err)
+  }
+}
@@ -0,0 +1,144 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use rand::rngs::StdRng;
+use rand::{SeedableRng, rng};
+
+use crate::cli::{flag_value, size_value};
+use crate::grid::Goal;
+use crate::{Difficulty, new_grid_for};
+
+/// How to print each generated puzzle.
+pub enum Format {
+  /// One position code per line.
+  Code,
+  /// Rows of numbers, with a blank line between puzzles.
+  Grid,
+}
+
+impl FromStr for Format {
+  type Err = ();
+
+  fn from_str(name: &str) -> Result<Self, ()> {
+    match name {
+      "code" => Ok(Format::Code),
+      "grid" => Ok(Format::Grid),
+      _ => Err(()),
+    }
+  }
+}
+
+/// Settings for the generate subcommand.
+pub struct GenerateOptions {
+  count: u32,
+  size: usize,
+  difficulty: Difficulty,
+  seed: Option<u64>,
+  format: Format,
+  output: Option<PathBuf>,
+}
+
+/// Parse the arguments that follow "generate" on the command line.
+pub fn parse_args(mut args: impl Iterator<Item = String>) -> Result<GenerateOptions, String> {
+  let mut options = GenerateOptions {
+    count: 1,
+    size: 4,
+    difficulty: Difficulty::Hard,
+    seed: None,
+    format: Format::Code,
+    output: None,
+  };
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "--count" => options.count = flag_value(&mut args, &arg)?,
+      "--size" => options.size = size_value(&mut args, &arg)?,
+      "--difficulty" => options.difficulty = flag_value(&mut args, &arg)?,
+      "--seed" => options.seed = Some(flag_value(&mut args, &arg)?),
+      "--format" => options.format = flag_value(&mut args, &arg)?,
+      "--output" => options.output = Some(flag_value(&mut args, &arg)?),
+      _ => return Err(format!("Unrecognized argument to generate: {arg:?}")),
+    }
+  }
+  Ok(options)
+}
+
+/// Print the requested number of puzzles. Every one is shuffled the same way the game
+/// shuffles, so they're all solvable.
+pub fn run(options: GenerateOptions) -> io::Result<()> {
+  let mut rng = match options.seed {
+    Some(seed) => StdRng::seed_from_u64(seed),
+    None => StdRng::from_rng(&mut rng()),
+  };
+  let mut out: Box<dyn Write> = match &options.output {
+    Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+    None => Box::new(io::stdout().lock()),
+  };
+  write_puzzles(&options, &mut rng, &mut out)?;
+  out.flush()
+}
+
+/// Write the puzzles to `out`, in the format asked for.
+fn write_puzzles(
+  options: &GenerateOptions,
+  rng: &mut StdRng,
+  out: &mut impl Write,
+) -> io::Result<()> {
+  for i in 0..options.count {
+    let grid = new_grid_for(options.size, &Goal::Standard, options.difficulty, rng);
+    match options.format {
+      Format::Code => writeln!(out, "{}", grid.code())?,
+      Format::Grid => {
+        if i > 0 {
+          writeln!(out)?;
+        }
+        write!(out, "{grid}")?;
+      }
+    }
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::grid::Grid;
+
+  /// The codes generate prints for the given arguments.
+  fn generate(args: &[&str]) -> Vec<Grid> {
+    let options = parse_args(args.iter().map(|arg| arg.to_string())).unwrap();
+    let mut rng = StdRng::seed_from_u64(options.seed.unwrap());
+    let mut out = Vec::new();
+    write_puzzles(&options, &mut rng, &mut out).unwrap();
+    String::from_utf8(out)
+      .unwrap()
+      .lines()
+      .map(|code| Grid::from_code(code).unwrap())
+      .collect()
+  }
+
+  #[test]
+  fn generated_puzzles_are_solvable_and_the_size_asked_for() {
+    for size in ["2", "3", "4", "5", "7", "10"] {
+      for difficulty in ["easy", "medium", "hard"] {
+        let args = [
+          "--size",
+          size,
+          "--difficulty",
+          difficulty,
+          "--seed",
+          "109",
+          "--count",
+          "20",
+        ];
+        let grids = generate(&args);
+        assert_eq!(grids.len(), 20, "{args:?}");
+        for grid in grids {
+          assert_eq!(grid.size().to_string(), size, "{args:?}");
+          assert!(grid.is_solvable(), "{args:?} gave\n{grid}");
+        }
+      }
+    }
+  }
+}
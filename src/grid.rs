@@ -0,0 +1,710 @@
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use rand::Rng;
+
+/// The smallest board we support, in tiles per side. A 2x2 board only has twelve
+/// positions it can reach, all within six moves of solved, but it's a fair warm-up.
+pub const MIN_SIZE: usize = 2;
+/// The largest board we support, in tiles per side.
+pub const MAX_SIZE: usize = 10;
+
+/// The direction a tile slides when a move is made. The blank goes the opposite way.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Direction {
+  Up,
+  Down,
+  Left,
+  Right,
+}
+
+impl Direction {
+  pub const ALL: [Direction; 4] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
+  ];
+
+  /// Where the tile that slides is, relative to the blank.
+  pub fn offset(self) -> (isize, isize) {
+    match self {
+      Direction::Up => (0, 1),
+      Direction::Down => (0, -1),
+      Direction::Left => (1, 0),
+      Direction::Right => (-1, 0),
+    }
+  }
+
+  /// The move that undoes this one.
+  pub fn opposite(self) -> Direction {
+    match self {
+      Direction::Up => Direction::Down,
+      Direction::Down => Direction::Up,
+      Direction::Left => Direction::Right,
+      Direction::Right => Direction::Left,
+    }
+  }
+
+  /// How this move looks on a board turned the given number of quarter turns
+  /// clockwise.
+  pub fn rotated(self, turns: u8) -> Direction {
+    (0..turns % 4).fold(self, |direction, _| match direction {
+      Direction::Up => Direction::Right,
+      Direction::Right => Direction::Down,
+      Direction::Down => Direction::Left,
+      Direction::Left => Direction::Up,
+    })
+  }
+
+  /// The direction for a letter in a written list of moves: u, d, l or r, in either
+  /// case.
+  pub fn from_letter(letter: char) -> Option<Direction> {
+    match letter.to_ascii_lowercase() {
+      'u' => Some(Direction::Up),
+      'd' => Some(Direction::Down),
+      'l' => Some(Direction::Left),
+      'r' => Some(Direction::Right),
+      _ => None,
+    }
+  }
+
+  /// The letter for this direction in a written list of moves.
+  pub fn letter(self) -> char {
+    match self {
+      Direction::Up => 'U',
+      Direction::Down => 'D',
+      Direction::Left => 'L',
+      Direction::Right => 'R',
+    }
+  }
+
+  pub fn name(self) -> &'static str {
+    match self {
+      Direction::Up => "up",
+      Direction::Down => "down",
+      Direction::Left => "left",
+      Direction::Right => "right",
+    }
+  }
+}
+
+/// Which arrangement of the tiles counts as solved.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub enum Goal {
+  /// Counting up from 1, row by row, with the blank at the end.
+  #[default]
+  Standard,
+  /// Counting down to 1, row by row, with the blank still at the end.
+  Reverse,
+  /// Any order the player likes, with the blank still at the end. Only fits a board
+  /// of the size it was given for.
+  Custom(Arc<[Cell]>),
+}
+
+impl Goal {
+  /// A goal of the player's own, read from a position code. The blank has to be in the
+  /// bottom right corner, as in the other goals, so the same boards can be solved.
+  pub fn custom(code: &str) -> Result<Goal, String> {
+    let grid = Grid::from_code(code).map_err(|err| err.to_string())?;
+    if grid.cells.last() != Some(&None) {
+      return Err("the blank (0) has to be last, in the bottom right corner".to_string());
+    }
+    Ok(Goal::Custom(grid.cells.into()))
+  }
+
+  /// The number that belongs at the given location in the solved puzzle.
+  pub fn target(&self, size: usize, x: usize, y: usize) -> Cell {
+    let i = y * size + x;
+    let last = size * size - 1;
+    match self {
+      _ if i == last => None,
+      Goal::Standard => Some(i as u8 + 1),
+      Goal::Reverse => Some((last - i) as u8),
+      Goal::Custom(cells) => cells[i],
+    }
+  }
+
+  /// The location (x, y) where the given tile belongs in the solved puzzle. Panics if
+  /// a custom goal is for a different size of board, so check `size` first.
+  pub fn position(&self, size: usize, n: u8) -> (usize, usize) {
+    let i = match self {
+      Goal::Standard => usize::from(n) - 1,
+      Goal::Reverse => size * size - 1 - usize::from(n),
+      Goal::Custom(cells) => cells.iter().position(|&cell| cell == Some(n)).unwrap(),
+    };
+    (i % size, i / size)
+  }
+
+  /// The location (x, y) where the blank belongs in the solved puzzle.
+  pub fn blank_position(&self, size: usize) -> (usize, usize) {
+    (size - 1, size - 1)
+  }
+
+  /// The size of board the goal is for, if it only fits one.
+  pub fn size(&self) -> Option<usize> {
+    match self {
+      Goal::Custom(cells) => Some(cells.len().isqrt()),
+      _ => None,
+    }
+  }
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      Goal::Standard => "standard",
+      Goal::Reverse => "reverse",
+      Goal::Custom(_) => "custom",
+    }
+  }
+
+  /// A name that tells goals apart, to keep records under. Custom goals include their
+  /// position code, since two of them can be different.
+  pub fn id(&self) -> String {
+    match self {
+      Goal::Custom(_) => {
+        let size = self.size().unwrap_or_default();
+        format!("custom-{}", Grid::solved(size, self.clone()).code())
+      }
+      goal => goal.name().to_string(),
+    }
+  }
+}
+
+impl FromStr for Goal {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "standard" => Ok(Goal::Standard),
+      "reverse" => Ok(Goal::Reverse),
+      _ => Err(format!("unknown goal {s:?} (expected standard or reverse)")),
+    }
+  }
+}
+
+/// Why a position code couldn't be read as a grid.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum CodeError {
+  /// The code has this many cells, which isn't a square board of a size we support.
+  Length(usize),
+  /// These characters aren't a tile on a board of this size.
+  Digit(String),
+  /// This tile, or the blank for '0', shows up more than once.
+  Repeated(String),
+}
+
+impl fmt::Display for CodeError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      CodeError::Length(len) => write!(
+        f,
+        "a code has one character per cell, or two on boards over 6x6, so it needs {} to \
+         {} characters or {} to {}, not {len}",
+        MIN_SIZE * MIN_SIZE,
+        6 * 6,
+        7 * 7 * 2,
+        MAX_SIZE * MAX_SIZE * 2
+      ),
+      CodeError::Digit(digits) => write!(f, "{digits:?} isn't a tile on a board this size"),
+      CodeError::Repeated(digits) if digits.chars().all(|c| c == '0') => {
+        write!(f, "the blank (0) shows up more than once")
+      }
+      CodeError::Repeated(digits) => write!(f, "tile {digits:?} shows up more than once"),
+    }
+  }
+}
+
+/// A square on the board: the number of the tile on it, or None for the blank.
+pub type Cell = Option<u8>;
+
+/// A square board of tiles with one blank.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Grid {
+  size: usize,
+  /// The cells row by row, starting from the top left.
+  cells: Vec<Cell>,
+  /// The arrangement the tiles have to end up in.
+  goal: Goal,
+}
+
+impl Grid {
+  /// The puzzle of the given size in its solved state.
+  pub fn solved(size: usize, goal: Goal) -> Grid {
+    let cells = (0..size * size)
+      .map(|i| goal.target(size, i % size, i / size))
+      .collect();
+    Grid { size, cells, goal }
+  }
+
+  /// Build a grid with the standard goal from a square array of rows.
+  pub fn from_rows<const N: usize>(rows: [[Cell; N]; N]) -> Grid {
+    Grid {
+      size: N,
+      cells: rows.concat(),
+      goal: Goal::Standard,
+    }
+  }
+
+  /// Read a grid back from its position code (see `code`), with the standard goal.
+  /// The code has to be a whole square board, from MIN_SIZE to MAX_SIZE, with every tile
+  /// exactly once.
+  pub fn from_code(code: &str) -> Result<Grid, CodeError> {
+    let chars: Vec<char> = code.chars().collect();
+    let size = (MIN_SIZE..=MAX_SIZE)
+      .find(|&size| size * size * code_digits(size) == chars.len())
+      .ok_or(CodeError::Length(chars.len()))?;
+    let len = size * size;
+    let mut seen = vec![false; len];
+    let mut cells = Vec::with_capacity(len);
+    for digits in chars.chunks(code_digits(size)) {
+      let n = digits
+        .iter()
+        .try_fold(0, |n, c| Some(n * 36 + c.to_digit(36)?))
+        .filter(|&n| (n as usize) < len)
+        .ok_or_else(|| CodeError::Digit(digits.iter().collect()))? as usize;
+      if seen[n] {
+        return Err(CodeError::Repeated(digits.iter().collect()));
+      }
+      seen[n] = true;
+      cells.push((n != 0).then_some(n as u8));
+    }
+    Ok(Grid {
+      size,
+      cells,
+      goal: Goal::Standard,
+    })
+  }
+
+  /// The same tiles in the same places, to be solved toward a different goal.
+  pub fn with_goal(self, goal: Goal) -> Grid {
+    Grid { goal, ..self }
+  }
+
+  /// The grid with every tile renumbered so that its place in this grid's goal becomes
+  /// its place in the given goal. The moves that solve one solve the other.
+  pub fn relabeled(&self, goal: Goal) -> Grid {
+    let cells = self
+      .cells
+      .iter()
+      .map(|&cell| {
+        let (x, y) = self.goal.position(self.size, cell?);
+        goal.target(self.size, x, y)
+      })
+      .collect();
+    Grid {
+      size: self.size,
+      cells,
+      goal,
+    }
+  }
+
+  /// The arrangement the tiles have to end up in.
+  pub fn goal(&self) -> &Goal {
+    &self.goal
+  }
+
+  /// The number of tiles on each side of the board.
+  pub fn size(&self) -> usize {
+    self.size
+  }
+
+  /// The number of tiles on the board, not counting the blank.
+  pub fn tile_count(&self) -> usize {
+    self.cells.len() - 1
+  }
+
+  pub fn rows(&self) -> impl Iterator<Item = &[Cell]> {
+    self.cells.chunks(self.size)
+  }
+
+  /// Returns the location (x, y) of the given tile, or of the blank for None.
+  pub fn find(&self, cell: Cell) -> (usize, usize) {
+    let i = self
+      .cells
+      .iter()
+      .position(|&c| c == cell)
+      .expect("Every tile and the blank are somewhere in the grid.");
+    (i % self.size, i / self.size)
+  }
+
+  /// Returns the location (x, y) of the blank square.
+  pub fn find_blank(&self) -> (usize, usize) {
+    self.find(None)
+  }
+
+  /// The location of the tile that would slide in the given direction, if there is one.
+  pub fn tile_to_slide(&self, direction: Direction) -> Option<(usize, usize)> {
+    let (x, y) = direction.offset();
+    let (blank_x, blank_y) = self.find_blank();
+    let tile_x = blank_x.checked_add_signed(x).filter(|&x| x < self.size)?;
+    let tile_y = blank_y.checked_add_signed(y).filter(|&y| y < self.size)?;
+    Some((tile_x, tile_y))
+  }
+
+  /// Slide a tile in the given direction if possible. If the given direction doesn't
+  /// work, do nothing. Returns whether a tile moved.
+  pub fn slide(&mut self, direction: Direction) -> bool {
+    let Some((tile_x, tile_y)) = self.tile_to_slide(direction) else {
+      // Illegal move; just ignore it.
+      return false;
+    };
+    let (blank_x, blank_y) = self.find_blank();
+    self
+      .cells
+      .swap(blank_y * self.size + blank_x, tile_y * self.size + tile_x);
+    true
+  }
+
+  /// The directions in which a tile can slide.
+  pub fn legal_moves(&self) -> Vec<Direction> {
+    Direction::ALL
+      .into_iter()
+      .filter(|&direction| self.tile_to_slide(direction).is_some())
+      .collect()
+  }
+
+  /// Check if every tile is in its goal position.
+  pub fn is_solved(&self) -> bool {
+    self.correct_tiles() == self.tile_count()
+  }
+
+  /// Whether every cell in row y, the blank included, holds what it should when solved.
+  pub fn row_complete(&self, y: usize) -> bool {
+    (0..self.size).all(|x| self.cells[y * self.size + x] == self.goal.target(self.size, x, y))
+  }
+
+  /// Whether every cell in column x, the blank included, holds what it should when solved.
+  pub fn column_complete(&self, x: usize) -> bool {
+    (0..self.size).all(|y| self.cells[y * self.size + x] == self.goal.target(self.size, x, y))
+  }
+
+  /// Whether each cell, row by row, holds the tile that belongs there. Always false for
+  /// the blank.
+  pub fn placed(&self) -> Vec<bool> {
+    self
+      .cells
+      .iter()
+      .enumerate()
+      .map(|(i, &cell)| {
+        cell.is_some() && cell == self.goal.target(self.size, i % self.size, i / self.size)
+      })
+      .collect()
+  }
+
+  /// How many tiles are in their goal positions.
+  pub fn correct_tiles(&self) -> usize {
+    self.placed().into_iter().filter(|&placed| placed).count()
+  }
+
+  /// The total distance of every tile from its goal position, counting only rows and
+  /// columns. It takes at least this many moves to solve the puzzle.
+  pub fn manhattan_distance(&self) -> usize {
+    (0..self.cells.len())
+      .map(|i| self.distance(i % self.size, i / self.size))
+      .sum()
+  }
+
+  /// How far the tile at (x, y) is from its goal position, counting only rows and
+  /// columns. The blank counts as being in place.
+  pub fn distance(&self, x: usize, y: usize) -> usize {
+    let Some(n) = self.cells[y * self.size + x] else {
+      return 0;
+    };
+    let (goal_x, goal_y) = self.goal.position(self.size, n);
+    x.abs_diff(goal_x) + y.abs_diff(goal_y)
+  }
+
+  /// How many pairs of tiles are in the opposite order from the goal, reading row by row
+  /// and skipping the blank. The solved puzzle has none. Moving sideways never changes
+  /// this, and moving up or down changes it by the number of tiles passed over.
+  pub fn inversions(&self) -> usize {
+    if self.goal != Goal::Standard {
+      // Counting only works toward the standard goal, so count as if that were it.
+      return self.relabeled(Goal::Standard).inversions();
+    }
+    let tiles: Vec<u8> = self.cells.iter().flatten().copied().collect();
+    (0..tiles.len())
+      .flat_map(|i| (i + 1..tiles.len()).map(move |j| (i, j)))
+      .filter(|&(i, j)| tiles[i] > tiles[j])
+      .count()
+  }
+
+  /// Whether the puzzle can be solved at all. Half of all arrangements can't.
+  /// See https://en.wikipedia.org/wiki/15_puzzle#Solvability
+  pub fn is_solvable(&self) -> bool {
+    let inversions = self.inversions();
+    let parity = if self.size.is_multiple_of(2) {
+      // On even boards, moving the blank up or down also changes the parity, so count
+      // how many rows it is away from its goal row at the bottom.
+      let (_, blank_y) = self.find_blank();
+      inversions + (self.size - 1 - blank_y)
+    } else {
+      inversions
+    };
+    parity % 2 == 0
+  }
+
+  /// Make an unsolvable board solvable by swapping its two highest tiles, like the 14
+  /// and 15 in Sam Loyd's famous unsolvable puzzle. Swapping any two tiles flips
+  /// whether a board can be solved. Returns the tiles swapped, or None if the board
+  /// could already be solved and was left alone.
+  pub fn repair(&mut self) -> Option<(u8, u8)> {
+    if self.is_solvable() {
+      return None;
+    }
+    let highest = (self.size * self.size - 1) as u8;
+    let (a, b) = (highest - 1, highest);
+    let find = |n| self.cells.iter().position(|&cell| cell == Some(n)).unwrap();
+    let (i, j) = (find(a), find(b));
+    self.cells.swap(i, j);
+    Some((a, b))
+  }
+
+  /// A short code identifying a position: one base-36 digit per cell, row by row, with
+  /// 0 for the blank. The solved 4x4 puzzle is "123456789abcdef0". Boards over 6x6 have
+  /// too many tiles for that, so they take two digits per cell.
+  pub fn code(&self) -> String {
+    let digits = code_digits(self.size);
+    let mut code = String::with_capacity(self.cells.len() * digits);
+    for cell in &self.cells {
+      let n = u32::from(cell.unwrap_or(0));
+      if digits == 2 {
+        code.push(char::from_digit(n / 36, 36).unwrap());
+      }
+      code.push(char::from_digit(n % 36, 36).unwrap());
+    }
+    code
+  }
+}
+
+/// How many base-36 digits each cell takes in the position code for a board of the given
+/// size.
+fn code_digits(size: usize) -> usize {
+  if size * size <= 36 { 1 } else { 2 }
+}
+
+/// Draws the grid as rows of numbers, with a dot for the blank.
+impl fmt::Display for Grid {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    for row in self.rows() {
+      let cells: Vec<_> = row
+        .iter()
+        .map(|cell| match cell {
+          Some(n) => format!("{n:>2}"),
+          None => " .".to_string(),
+        })
+        .collect();
+      writeln!(f, "{}", cells.join(" "))?;
+    }
+    Ok(())
+  }
+}
+
+/// Create a new grid shuffled using the given source of randomness. Passing a seeded
+/// RNG gives the same grid every time.
+pub fn new_grid_with(size: usize, goal: Goal, rng: &mut impl Rng) -> Grid {
+  let tiles = size * size - 1;
+  let mut numbers: Vec<u8> = (1..=tiles as u8).collect();
+
+  // If you just shuffle the array, there's a 50% chance the puzzle is unsolvable.
+  // Instead, do an even number of exchanges. According to
+  // https://en.wikipedia.org/wiki/15_puzzle#Solvability this should produce a
+  // solvable arrangement. For our even number, use 50 which should be high enough.
+  let mut swaps = 50;
+  while swaps > 0 {
+    let a = rng.random_range(0..tiles);
+    let b = rng.random_range(0..tiles);
+    if a == b {
+      continue;
+    }
+
+    numbers.swap(a, b);
+    swaps -= 1;
+  }
+
+  let cells = numbers.into_iter().map(Some).chain([None]).collect();
+  // The shuffle is solvable toward the standard goal, so carry it over to this one.
+  let grid = Grid {
+    size,
+    cells,
+    goal: Goal::Standard,
+  }
+  .relabeled(goal.clone());
+  // With only three tiles on a 2x2 board, a third of shuffles put them all back in
+  // place, so shuffle again rather than start out solved.
+  if grid.is_solved() {
+    return new_grid_with(size, goal, rng);
+  }
+  grid
+}
+
+/// Create a grid by making random legal moves, starting from the solved puzzle. This
+/// keeps the puzzle within that many moves of solved. A move never immediately undoes
+/// the one before it.
+pub fn scramble_grid(size: usize, goal: Goal, rng: &mut impl Rng, moves: u32) -> Grid {
+  let mut grid = Grid::solved(size, goal);
+  let mut last: Option<Direction> = None;
+  for _ in 0..moves {
+    let direction = random_move(&grid, last, rng);
+    grid.slide(direction);
+    last = Some(direction);
+  }
+  // On a 2x2 board the blank can only go round in a circle, and every twelfth move
+  // brings it back to solved. One more move gets it out again.
+  if moves > 0 && grid.is_solved() {
+    grid.slide(random_move(&grid, last, rng));
+  }
+  grid
+}
+
+/// Pick a random legal move, other than the one that would undo the last move.
+pub fn random_move(grid: &Grid, last: Option<Direction>, rng: &mut impl Rng) -> Direction {
+  let options: Vec<_> = grid
+    .legal_moves()
+    .into_iter()
+    .filter(|&direction| Some(direction.opposite()) != last)
+    .collect();
+  options[rng.random_range(0..options.len())]
+}
+
+/// Where a cell shows on a board of the given size turned the given number of quarter
+/// turns clockwise.
+pub fn rotate_cell(size: usize, (x, y): (usize, usize), turns: u8) -> (usize, usize) {
+  (0..turns % 4).fold((x, y), |(x, y), _| (size - 1 - y, x))
+}
+
+#[cfg(test)]
+mod tests {
+  use rand::SeedableRng;
+  use rand::rngs::StdRng;
+
+  use super::*;
+
+  #[test]
+  fn descending_board_solves_the_reverse_goal() {
+    let grid = Grid::from_rows([
+      [Some(8), Some(7), Some(6)],
+      [Some(5), Some(4), Some(3)],
+      [Some(2), Some(1), None],
+    ])
+    .with_goal(Goal::Reverse);
+    assert!(grid.is_solved());
+    assert_eq!(grid, Grid::solved(3, Goal::Reverse));
+    assert!(
+      !Grid::solved(3, Goal::Standard)
+        .with_goal(Goal::Reverse)
+        .is_solved()
+    );
+  }
+
+  #[test]
+  fn reverse_goal_boards_can_be_solved() {
+    for size in MIN_SIZE..=MAX_SIZE {
+      assert!(Grid::solved(size, Goal::Reverse).is_solvable());
+      for seed in 0..10 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let shuffled = new_grid_with(size, Goal::Reverse, &mut rng);
+        assert!(
+          shuffled.is_solvable(),
+          "{size}x{size} seed {seed}:\n{shuffled}"
+        );
+        let scrambled = scramble_grid(size, Goal::Reverse, &mut rng, 50);
+        assert!(
+          scrambled.is_solvable(),
+          "{size}x{size} seed {seed}:\n{scrambled}"
+        );
+      }
+    }
+  }
+
+  #[test]
+  fn codes_round_trip() {
+    let grid = Grid::from_code("1234567890abcdef").unwrap();
+    assert_eq!(grid.code(), "1234567890abcdef");
+    let big = Grid::solved(7, Goal::Standard);
+    assert_eq!(Grid::from_code(&big.code()), Ok(big));
+  }
+
+  #[test]
+  fn codes_of_the_wrong_length_are_rejected() {
+    assert_eq!(Grid::from_code(""), Err(CodeError::Length(0)));
+    assert_eq!(Grid::from_code("12345678"), Err(CodeError::Length(8)));
+    assert_eq!(
+      Grid::from_code("123456789abcdef0f"),
+      Err(CodeError::Length(17))
+    );
+  }
+
+  #[test]
+  fn codes_with_tiles_too_big_are_rejected() {
+    assert_eq!(
+      Grid::from_code("12345678g"),
+      Err(CodeError::Digit("g".to_string()))
+    );
+    assert_eq!(
+      Grid::from_code("1234-6780"),
+      Err(CodeError::Digit("-".to_string()))
+    );
+  }
+
+  #[test]
+  fn codes_with_repeated_tiles_are_rejected() {
+    assert_eq!(
+      Grid::from_code("123345670"),
+      Err(CodeError::Repeated("3".to_string()))
+    );
+    assert_eq!(
+      Grid::from_code("123456700"),
+      Err(CodeError::Repeated("0".to_string()))
+    );
+  }
+
+  #[test]
+  fn custom_goals_are_read_from_codes() {
+    let goal = Goal::custom("147258360").unwrap();
+    assert_eq!(goal.size(), Some(3));
+    assert_eq!(goal.target(3, 1, 0), Some(4));
+    assert_eq!(goal.position(3, 8), (2, 1));
+    assert_eq!(goal.position(3, 3), (0, 2));
+    let solved = Grid::from_code("147258360")
+      .unwrap()
+      .with_goal(goal.clone());
+    assert!(solved.is_solved());
+    assert_eq!(solved, Grid::solved(3, goal));
+  }
+
+  #[test]
+  fn custom_goals_need_the_blank_last() {
+    let err = Goal::custom("147258036").unwrap_err();
+    assert!(err.contains("has to be last"), "{err}");
+  }
+
+  #[test]
+  fn custom_goals_need_a_whole_board() {
+    assert_eq!(
+      Goal::custom("14725836"),
+      Err(CodeError::Length(8).to_string())
+    );
+    assert_eq!(
+      Goal::custom("147258340"),
+      Err(CodeError::Repeated("4".to_string()).to_string())
+    );
+    assert_eq!(
+      Goal::custom("14725836x"),
+      Err(CodeError::Digit("x".to_string()).to_string())
+    );
+  }
+
+  #[test]
+  fn custom_goal_boards_can_be_solved() {
+    let goal = Goal::custom("fedcba9876543210").unwrap();
+    for seed in 0..20 {
+      let mut rng = StdRng::seed_from_u64(seed);
+      let shuffled = new_grid_with(4, goal.clone(), &mut rng);
+      assert!(shuffled.is_solvable(), "seed {seed}:\n{shuffled}");
+      let scrambled = scramble_grid(4, goal.clone(), &mut rng, 50);
+      assert!(scrambled.is_solvable(), "seed {seed}:\n{scrambled}");
+    }
+  }
+}
@@ -0,0 +1,557 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use crossterm::event::KeyCode;
+
+use crate::config::Movement;
+use crate::grid::Direction;
+
+/// Prefix shared by all the environment variables we read key bindings from.
+const ENV_PREFIX: &str = "SLYDE_";
+
+/// The environment variables that can override a key binding, and the action each one controls.
+pub const ENV_VARS: [(&str, Action); 28] = [
+  ("SLYDE_QUIT_KEY", Action::Quit),
+  ("SLYDE_RESTART_KEY", Action::Restart),
+  ("SLYDE_UNDO_KEY", Action::Undo),
+  ("SLYDE_REWIND_KEY", Action::Rewind),
+  ("SLYDE_CHECKPOINT_KEY", Action::Checkpoint),
+  ("SLYDE_HINT_KEY", Action::Hint),
+  ("SLYDE_STUDY_KEY", Action::Study),
+  ("SLYDE_LOCK_KEY", Action::Lock),
+  ("SLYDE_FINISH_ROW_KEY", Action::FinishRow),
+  ("SLYDE_BLANK_KEY", Action::MoveBlank),
+  ("SLYDE_GOALS_KEY", Action::ToggleGoals),
+  ("SLYDE_LINES_KEY", Action::ToggleLines),
+  ("SLYDE_GHOSTS_KEY", Action::ToggleGhosts),
+  ("SLYDE_TRAIL_KEY", Action::ToggleTrail),
+  ("SLYDE_INVERSIONS_KEY", Action::ToggleInversions),
+  ("SLYDE_INSTRUCTIONS_KEY", Action::ToggleInstructions),
+  ("SLYDE_IDLE_KEY", Action::ToggleIdle),
+  ("SLYDE_HISTORY_KEY", Action::History),
+  ("SLYDE_BROWSE_KEY", Action::Browse),
+  ("SLYDE_EXPORT_KEY", Action::Export),
+  ("SLYDE_SHARE_KEY", Action::Share),
+  ("SLYDE_SHOWCASE_KEY", Action::Showcase),
+  ("SLYDE_THEME_KEY", Action::NextTheme),
+  ("SLYDE_ROTATE_KEY", Action::RotateView),
+  ("SLYDE_UP_KEY", Action::Up),
+  ("SLYDE_DOWN_KEY", Action::Down),
+  ("SLYDE_LEFT_KEY", Action::Left),
+  ("SLYDE_RIGHT_KEY", Action::Right),
+];
+
+/// Something the player can do with a key press.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+  Quit,
+  Restart,
+  /// Restart at the next difficulty up. Bound to the uppercase restart key.
+  Harder,
+  Undo,
+  /// Undo several moves at once.
+  Rewind,
+  Checkpoint,
+  /// Go back to the checkpoint. Bound to the uppercase checkpoint key.
+  ToCheckpoint,
+  Hint,
+  /// Make the solver's next move, to study a solution at the player's own pace.
+  Study,
+  /// Lock every tile that's in place, so moves that would disturb it are refused.
+  Lock,
+  /// Unlock every tile. Bound to the uppercase lock key.
+  Unlock,
+  /// Make the last few moves of a row that's one tile short, with --row-assist.
+  FinishRow,
+  /// Pick a cell and walk the blank there.
+  MoveBlank,
+  ToggleGoals,
+  /// Show the goal labels only while the key is held. Bound to the uppercase goals key.
+  PeekGoals,
+  /// Mark the rows and columns that are finished.
+  ToggleLines,
+  /// Show faintly which number belongs in each cell.
+  ToggleGhosts,
+  /// Show where the blank has just been.
+  ToggleTrail,
+  /// Show how many pairs of tiles are out of order.
+  ToggleInversions,
+  /// Hide the line of key instructions, to give the board the room.
+  ToggleInstructions,
+  /// Show how long it's been since the last move.
+  ToggleIdle,
+  /// Show the puzzles solved this session.
+  History,
+  /// Look through seeded puzzles to pick one to play.
+  Browse,
+  /// Save the moves made so far to a file.
+  Export,
+  /// Show the position as a QR code, to photograph and share.
+  Share,
+  /// Scramble and solve the board on its own, to show how it works. Only from a solved
+  /// board.
+  Showcase,
+  /// Switch to the next theme, to try them out while playing.
+  NextTheme,
+  /// Turn the board a quarter turn clockwise on screen. Only the view turns, so the
+  /// goal stays the same.
+  RotateView,
+  /// Turn the board back a quarter turn. Bound to the uppercase rotate key.
+  RotateBack,
+  Up,
+  Down,
+  Left,
+  Right,
+  /// Slide every tile between the blank and the edge in one go. Bound to the uppercase
+  /// movement keys.
+  SlideAll(Direction),
+}
+
+impl Action {
+  /// What the action does, to finish a sentence like "it will ...".
+  pub fn name(self) -> &'static str {
+    match self {
+      Action::Quit => "quit",
+      Action::Restart => "restart",
+      Action::Harder => "restart one level harder",
+      Action::Undo => "undo",
+      Action::Rewind => "rewind",
+      Action::Checkpoint => "set a checkpoint",
+      Action::ToCheckpoint => "go back to the checkpoint",
+      Action::Hint => "show a hint",
+      Action::Study => "study the solution a move at a time",
+      Action::Lock => "lock placed tiles",
+      Action::Unlock => "unlock every tile",
+      Action::FinishRow => "finish a row",
+      Action::MoveBlank => "move the blank",
+      Action::ToggleGoals => "toggle goal labels",
+      Action::PeekGoals => "show goal labels while held",
+      Action::ToggleLines => "toggle line checks",
+      Action::ToggleGhosts => "toggle ghost tiles",
+      Action::ToggleTrail => "toggle the blank's trail",
+      Action::ToggleInversions => "toggle the inversion count",
+      Action::ToggleInstructions => "toggle the instructions line",
+      Action::ToggleIdle => "toggle the idle time",
+      Action::History => "show the history",
+      Action::Browse => "browse seeds",
+      Action::Export => "save the moves",
+      Action::Share => "show a code to share the position",
+      Action::Showcase => "start a showcase",
+      Action::NextTheme => "switch to the next theme",
+      Action::RotateView => "turn the board clockwise",
+      Action::RotateBack => "turn the board back",
+      Action::Up => "move up",
+      Action::Down => "move down",
+      Action::Left => "move left",
+      Action::Right => "move right",
+      Action::SlideAll(Direction::Up) => "slide a whole column up",
+      Action::SlideAll(Direction::Down) => "slide a whole column down",
+      Action::SlideAll(Direction::Left) => "slide a whole row left",
+      Action::SlideAll(Direction::Right) => "slide a whole row right",
+    }
+  }
+}
+
+/// The letter keys bound to each action. The arrow keys always move, regardless of these.
+pub struct KeyBindings {
+  pub quit: char,
+  pub restart: char,
+  pub undo: char,
+  pub rewind: char,
+  pub checkpoint: char,
+  pub hint: char,
+  pub study: char,
+  pub lock: char,
+  pub finish_row: char,
+  pub blank: char,
+  pub goals: char,
+  pub lines: char,
+  pub ghosts: char,
+  pub trail: char,
+  pub inversions: char,
+  pub instructions: char,
+  pub idle: char,
+  pub history: char,
+  pub browse: char,
+  pub export: char,
+  pub share: char,
+  pub showcase: char,
+  pub theme: char,
+  pub rotate: char,
+  pub up: char,
+  pub down: char,
+  pub left: char,
+  pub right: char,
+}
+
+impl Default for KeyBindings {
+  fn default() -> Self {
+    KeyBindings {
+      quit: 'q',
+      restart: 'r',
+      undo: 'u',
+      rewind: 'b',
+      checkpoint: 'c',
+      hint: 'h',
+      study: 'n',
+      lock: 'k',
+      finish_row: 'f',
+      blank: 'm',
+      goals: 'g',
+      lines: 'l',
+      ghosts: 'e',
+      trail: 't',
+      inversions: 'i',
+      instructions: '?',
+      idle: '.',
+      history: 'y',
+      browse: 'o',
+      export: 'x',
+      share: 'j',
+      showcase: 'p',
+      theme: 'v',
+      rotate: 'z',
+      up: 'w',
+      down: 's',
+      left: 'a',
+      right: 'd',
+    }
+  }
+}
+
+/// A named set of key bindings to start from, before any environment overrides.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Preset {
+  #[default]
+  Standard,
+  /// Everything within reach of the right hand, with IJKL to move.
+  OneHanded,
+}
+
+impl Preset {
+  pub const ALL: [Preset; 2] = [Preset::Standard, Preset::OneHanded];
+
+  pub fn name(self) -> &'static str {
+    match self {
+      Preset::Standard => "standard",
+      Preset::OneHanded => "one-handed",
+    }
+  }
+
+  pub fn bindings(self) -> KeyBindings {
+    match self {
+      Preset::Standard => KeyBindings::default(),
+      Preset::OneHanded => KeyBindings {
+        quit: ']',
+        restart: 'o',
+        undo: 'u',
+        rewind: 'y',
+        checkpoint: 'p',
+        hint: 'h',
+        study: '8',
+        lock: 'b',
+        finish_row: '0',
+        blank: 'n',
+        goals: 'm',
+        lines: ';',
+        ghosts: '\'',
+        trail: ',',
+        inversions: '\\',
+        instructions: '?',
+        idle: '6',
+        history: '.',
+        browse: '/',
+        export: '-',
+        share: '7',
+        showcase: '[',
+        theme: '=',
+        rotate: '9',
+        up: 'i',
+        down: 'k',
+        left: 'j',
+        right: 'l',
+      },
+    }
+  }
+}
+
+impl FromStr for Preset {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Preset::ALL
+      .into_iter()
+      .find(|preset| preset.name() == s)
+      .ok_or_else(|| format!("unknown key preset {s:?} (expected standard or one-handed)"))
+  }
+}
+
+impl KeyBindings {
+  /// Start from the preset, then apply the config file's keys table, then any overrides
+  /// found in the environment. Returns the bindings along with warnings about any
+  /// entries or variables that couldn't be used.
+  pub fn load(preset: Preset, config: &BTreeMap<String, String>) -> (Self, Vec<String>) {
+    let mut bindings = preset.bindings();
+    let mut warnings = Vec::new();
+    // Each override that was applied, with the key it replaced.
+    let mut overrides = Vec::new();
+
+    for (name, value) in config {
+      let Some(&(_, action)) = ENV_VARS.iter().find(|(var, _)| config_name(var) == *name) else {
+        warnings.push(format!(
+          "Ignoring keys.{name}: there's no action by that name"
+        ));
+        continue;
+      };
+      match parse_key(value) {
+        Some(key) => *bindings.key_mut(action) = key,
+        None => warnings.push(format!(
+          "Ignoring keys.{name} = {value:?}: expected a single printable character"
+        )),
+      }
+    }
+
+    for (name, value) in std::env::vars() {
+      if !name.starts_with(ENV_PREFIX) || !name.ends_with("_KEY") {
+        continue;
+      }
+      let Some(&(var, action)) = ENV_VARS.iter().find(|(var, _)| *var == name) else {
+        warnings.push(format!("Ignoring unrecognized variable {name}"));
+        continue;
+      };
+      match parse_key(&value) {
+        Some(key) => {
+          let previous = std::mem::replace(bindings.key_mut(action), key);
+          overrides.push((var, action, previous));
+        }
+        None => warnings.push(format!(
+          "Ignoring {name}={value:?}: expected a single printable character"
+        )),
+      }
+    }
+
+    // A key can only do one thing, so drop any override that gives an action a key
+    // another one has, starting from the actions listed last. Putting the old key back
+    // can clash with a different override, so go again until nothing clashes.
+    overrides.sort_by_key(|&(var, _, _)| ENV_VARS.iter().position(|&(v, _)| v == var));
+    loop {
+      let keys: Vec<_> = ENV_VARS
+        .iter()
+        .map(|&(var, action)| (var, *bindings.key_mut(action)))
+        .collect();
+      let clash = overrides
+        .iter()
+        .enumerate()
+        .rev()
+        .find_map(|(i, &(var, action, _))| {
+          let key = *bindings.key_mut(action);
+          let &(other, _) = keys.iter().find(|&&(v, k)| v != var && k == key)?;
+          Some((i, key, other))
+        });
+      let Some((i, key, other)) = clash else {
+        break;
+      };
+      let (var, action, previous) = overrides.remove(i);
+      *bindings.key_mut(action) = previous;
+      warnings.push(format!(
+        "Ignoring {var}={key:?}: that key is already used by {other}"
+      ));
+    }
+
+    warnings.extend(bindings.conflicts());
+    (bindings, warnings)
+  }
+
+  fn key_mut(&mut self, action: Action) -> &mut char {
+    match action {
+      Action::Quit => &mut self.quit,
+      Action::Restart | Action::Harder => &mut self.restart,
+      Action::Undo => &mut self.undo,
+      Action::Rewind => &mut self.rewind,
+      Action::Checkpoint | Action::ToCheckpoint => &mut self.checkpoint,
+      Action::Hint => &mut self.hint,
+      Action::Study => &mut self.study,
+      Action::Lock | Action::Unlock => &mut self.lock,
+      Action::FinishRow => &mut self.finish_row,
+      Action::MoveBlank => &mut self.blank,
+      Action::ToggleGoals | Action::PeekGoals => &mut self.goals,
+      Action::ToggleLines => &mut self.lines,
+      Action::ToggleGhosts => &mut self.ghosts,
+      Action::ToggleTrail => &mut self.trail,
+      Action::ToggleInversions => &mut self.inversions,
+      Action::ToggleInstructions => &mut self.instructions,
+      Action::ToggleIdle => &mut self.idle,
+      Action::History => &mut self.history,
+      Action::Browse => &mut self.browse,
+      Action::Export => &mut self.export,
+      Action::Share => &mut self.share,
+      Action::Showcase => &mut self.showcase,
+      Action::NextTheme => &mut self.theme,
+      Action::RotateView | Action::RotateBack => &mut self.rotate,
+      Action::Up => &mut self.up,
+      Action::Down => &mut self.down,
+      Action::Left => &mut self.left,
+      Action::Right => &mut self.right,
+      Action::SlideAll(Direction::Up) => &mut self.up,
+      Action::SlideAll(Direction::Down) => &mut self.down,
+      Action::SlideAll(Direction::Left) => &mut self.left,
+      Action::SlideAll(Direction::Right) => &mut self.right,
+    }
+  }
+
+  /// Every binding, the way the config file's keys table names them.
+  pub fn config_table(&self) -> BTreeMap<String, String> {
+    let letters = self.letters();
+    ENV_VARS
+      .iter()
+      .filter_map(|&(var, action)| {
+        let &(key, _) = letters.iter().find(|&&(_, a)| a == action)?;
+        Some((config_name(var), key.to_string()))
+      })
+      .collect()
+  }
+
+  /// The letter bound to each action, in the order they're looked up.
+  pub fn letters(&self) -> [(char, Action); 28] {
+    [
+      (self.quit, Action::Quit),
+      (self.restart, Action::Restart),
+      (self.undo, Action::Undo),
+      (self.rewind, Action::Rewind),
+      (self.checkpoint, Action::Checkpoint),
+      (self.hint, Action::Hint),
+      (self.study, Action::Study),
+      (self.lock, Action::Lock),
+      (self.finish_row, Action::FinishRow),
+      (self.blank, Action::MoveBlank),
+      (self.goals, Action::ToggleGoals),
+      (self.lines, Action::ToggleLines),
+      (self.ghosts, Action::ToggleGhosts),
+      (self.trail, Action::ToggleTrail),
+      (self.inversions, Action::ToggleInversions),
+      (self.instructions, Action::ToggleInstructions),
+      (self.idle, Action::ToggleIdle),
+      (self.history, Action::History),
+      (self.browse, Action::Browse),
+      (self.export, Action::Export),
+      (self.share, Action::Share),
+      (self.showcase, Action::Showcase),
+      (self.theme, Action::NextTheme),
+      (self.rotate, Action::RotateView),
+      (self.up, Action::Up),
+      (self.down, Action::Down),
+      (self.left, Action::Left),
+      (self.right, Action::Right),
+    ]
+  }
+
+  /// The actions bound to the uppercase of another action's letter. These come after
+  /// `letters`, so a letter bound directly wins. Keys with no uppercase are left out.
+  fn shifted(&self) -> Vec<(char, Action)> {
+    [
+      (self.restart, Action::Harder),
+      (self.checkpoint, Action::ToCheckpoint),
+      (self.lock, Action::Unlock),
+      (self.goals, Action::PeekGoals),
+      (self.rotate, Action::RotateBack),
+      (self.up, Action::SlideAll(Direction::Up)),
+      (self.down, Action::SlideAll(Direction::Down)),
+      (self.left, Action::SlideAll(Direction::Left)),
+      (self.right, Action::SlideAll(Direction::Right)),
+    ]
+    .into_iter()
+    .filter(|(key, _)| key.to_ascii_uppercase() != *key)
+    .map(|(key, action)| (key.to_ascii_uppercase(), action))
+    .collect()
+  }
+
+  /// Warnings about keys bound to more than one action. Only the first action looked up
+  /// gets the key, though the arrow keys always move no matter what the letters do.
+  fn conflicts(&self) -> Vec<String> {
+    let bindings: Vec<_> = self.letters().into_iter().chain(self.shifted()).collect();
+    let mut warnings = Vec::new();
+    for (i, &(key, action)) in bindings.iter().enumerate() {
+      let Some(&(_, winner)) = bindings[..i].iter().find(|(k, _)| *k == key) else {
+        continue;
+      };
+      let mut warning = format!(
+        "{key:?} is bound to both {} and {}, so it will {}",
+        winner.name(),
+        action.name(),
+        winner.name()
+      );
+      if matches!(
+        action,
+        Action::Up | Action::Down | Action::Left | Action::Right
+      ) {
+        warning.push_str(" (the arrow keys still move)");
+      }
+      warnings.push(warning);
+    }
+    warnings
+  }
+
+  /// Whether the key is the goals key, in either case. A peek ends when it's let go,
+  /// whether or not Shift is let go first.
+  pub fn is_goals_key(&self, code: KeyCode) -> bool {
+    matches!(code, KeyCode::Char(c) if c.eq_ignore_ascii_case(&self.goals))
+  }
+
+  /// Look up which action, if any, a key is bound to.
+  pub fn action(&self, code: KeyCode) -> Option<Action> {
+    match code {
+      KeyCode::Up => Some(Action::Up),
+      KeyCode::Down => Some(Action::Down),
+      KeyCode::Left => Some(Action::Left),
+      KeyCode::Right => Some(Action::Right),
+      KeyCode::Char(c) => self
+        .letters()
+        .into_iter()
+        .chain(self.shifted())
+        .find(|(key, _)| *key == c)
+        .map(|(_, action)| action),
+      _ => None,
+    }
+  }
+
+  /// A one-line summary of the controls, for the instructions line. It says when the
+  /// movement keys move the blank, since most games have them move tiles.
+  pub fn instructions(&self, movement: Movement) -> String {
+    let keys: String = [self.up, self.left, self.down, self.right]
+      .iter()
+      .map(|c| c.to_ascii_uppercase())
+      .collect();
+    let key = |c: char| c.to_ascii_uppercase();
+    let moves = match movement {
+      Movement::Tiles => "Move",
+      Movement::Blank => "Move blank",
+    };
+    format!(
+      "{moves}: arrows/{keys}  {}: undo  {}: restart  {}: hint  {}: goals  {}: showcase  {}: quit",
+      key(self.undo),
+      key(self.restart),
+      key(self.hint),
+      key(self.goals),
+      key(self.showcase),
+      key(self.quit)
+    )
+  }
+}
+
+/// The name of an action in the config file's keys table: its environment variable's
+/// name in lowercase, without the prefix and suffix.
+fn config_name(var: &str) -> String {
+  var
+    .trim_start_matches(ENV_PREFIX)
+    .trim_end_matches("_KEY")
+    .to_lowercase()
+}
+
+/// Parse a key binding from an environment variable's value.
+fn parse_key(value: &str) -> Option<char> {
+  let mut chars = value.chars();
+  match (chars.next(), chars.next()) {
+    (Some(c), None) if !c.is_whitespace() && !c.is_control() => Some(c),
+    _ => None,
+  }
+}
@@ -0,0 +1,110 @@
+use ratatui::layout::Rect;
+
+use crate::Align;
+use crate::config::TileStyle;
+
+/// Sizes and spacing for drawing the board, in terminal cells.
+#[derive(Clone, Copy)]
+pub struct LayoutConfig {
+  /// The distance from one tile to the next, including any line between them.
+  pub tile_width: u16,
+  pub tile_height: u16,
+  /// How wide the line shared by neighboring tiles is. Bordered tiles sit right next to
+  /// each other, with nothing shared.
+  pub gap: u16,
+  /// Space between the edges of the main area and the board's border.
+  pub margin_x: u16,
+  pub margin_y: u16,
+  /// Space between the board's border and the tiles.
+  pub padding_x: u16,
+  pub padding_y: u16,
+}
+
+impl LayoutConfig {
+  /// The layout for a board with the given number of tiles per side. Big boards get
+  /// narrower tiles and less space around them, so they still fit in a normal terminal.
+  pub fn for_size(size: usize, style: TileStyle) -> Self {
+    let layout = if size >= 5 {
+      LayoutConfig {
+        tile_width: 5,
+        tile_height: 3,
+        gap: 0,
+        margin_x: 2,
+        margin_y: 1,
+        padding_x: 1,
+        padding_y: 0,
+      }
+    } else {
+      LayoutConfig {
+        tile_width: 6,
+        tile_height: 3,
+        gap: 0,
+        margin_x: 6,
+        margin_y: 2,
+        padding_x: 2,
+        padding_y: 0,
+      }
+    };
+    match style {
+      TileStyle::Bordered => layout,
+      // Tiles are only as tall as a bordered tile's inside, plus one line to share, and
+      // four cells wide so two digits sit in the middle. The board's own border closes
+      // off the outer tiles.
+      TileStyle::Grid => LayoutConfig {
+        tile_width: 5,
+        tile_height: layout.tile_height - 1,
+        gap: 1,
+        padding_x: 0,
+        padding_y: 0,
+        ..layout
+      },
+    }
+  }
+
+  /// Where the board goes, border included, within the main area.
+  pub fn board_area(self, main_area: Rect, size: u16, align: Align) -> Rect {
+    let width = self.tile_width * size - self.gap + 2 * (self.padding_x + 1);
+    let margin_x = match align {
+      Align::Left => self.margin_x,
+      // Never closer to the edge than the left-aligned board would be.
+      Align::Center => self.margin_x.max(main_area.width.saturating_sub(width) / 2),
+    };
+    Rect {
+      x: main_area.x + margin_x,
+      y: main_area.y + self.margin_y,
+      width,
+      height: self.tile_height * size - self.gap + 2 * (self.padding_y + 1),
+    }
+  }
+
+  /// Where the board goes when only the given number of columns and rows of it fit on
+  /// screen.
+  pub fn view_area(self, main_area: Rect, (columns, rows): (u16, u16), align: Align) -> Rect {
+    Rect {
+      height: self.board_area(main_area, rows, align).height,
+      ..self.board_area(main_area, columns, align)
+    }
+  }
+
+  /// The smallest width and height that holds the whole game, with the board
+  /// left-aligned and the given number of lines around the main area.
+  pub fn min_size(self, size: u16, lines: u16) -> (u16, u16) {
+    let board = self.board_area(Rect::default(), size, Align::Left);
+    (board.right(), self.main_height(size) + lines)
+  }
+
+  /// How tall the main area needs to be to hold the board.
+  pub fn main_height(self, size: u16) -> u16 {
+    self.margin_y + self.board_area(Rect::default(), size, Align::Left).height
+  }
+
+  /// The area of the tile in the given column and row, given the board's area.
+  pub fn tile(self, board_area: Rect, x: usize, y: usize) -> Rect {
+    Rect {
+      x: board_area.x + 1 + self.padding_x + x as u16 * self.tile_width,
+      y: board_area.y + 1 + self.padding_y + y as u16 * self.tile_height,
+      width: self.tile_width - self.gap,
+      height: self.tile_height - self.gap,
+    }
+  }
+}
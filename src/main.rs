@@ -1,247 +1,1003 @@
-use std::{array, io};
-
-use crossterm::event::{self, Event, KeyCode, KeyEventKind};
-use rand::{Rng, rng};
-use ratatui::buffer::Buffer;
-use ratatui::layout::{Constraint, Layout, Rect};
-use ratatui::style::{Color, Style, Stylize};
-use ratatui::symbols::border;
-use ratatui::text::Line;
-use ratatui::widgets::{Block, Widget};
-use ratatui::{DefaultTerminal, Frame};
-
-const TILE_WIDTH: u16 = 6;
-const TILE_HEIGHT: u16 = 3;
-
-fn main() -> io::Result<()> {
-  let demo_mode = parse_args();
-  let mode = if demo_mode {
-    Mode::Demo
-  } else {
-    Mode::Standard
+mod app;
+mod bench;
+mod cli;
+mod config;
+mod date;
+mod error;
+mod generate;
+mod grid;
+mod keys;
+mod layout;
+mod notation;
+mod qr;
+mod settings;
+mod snapshot;
+mod solver;
+mod stats;
+mod status_bar;
+mod title;
+
+use std::collections::VecDeque;
+use std::io::{self, Read};
+use std::iter;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::str::FromStr;
+
+use crossterm::event::{
+  DisableBracketedPaste, DisableFocusChange, DisableMouseCapture, EnableBracketedPaste,
+  EnableFocusChange, EnableMouseCapture, KeyboardEnhancementFlags, PopKeyboardEnhancementFlags,
+  PushKeyboardEnhancementFlags,
+};
+use crossterm::{execute, terminal};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng, rng};
+use ratatui::style::Color;
+
+use crate::app::App;
+use crate::bench::BenchOptions;
+use crate::cli::parse_args;
+use crate::config::{Config, Defaults, TileColorScheme, WinScreen};
+use crate::date::Date;
+use crate::error::Error;
+use crate::generate::GenerateOptions;
+use crate::grid::{Direction, Goal, Grid, new_grid_with, scramble_grid};
+use crate::keys::{KeyBindings, Preset};
+use crate::notation::MoveList;
+use crate::settings::{SETTINGS_FILE, Settings};
+use crate::stats::{BestMoves, STATS_FILES, data_dir};
+
+/// How many times to shuffle looking for a hard enough puzzle, before settling for the
+/// last one.
+const MAX_SHUFFLES: usize = 20;
+
+/// How many solves in a row at one difficulty have to be much easier or harder than
+/// expected before suggesting another.
+const ADVICE_SOLVES: usize = 5;
+
+/// How many moves a rewind takes back.
+const REWIND_MOVES: usize = 5;
+
+/// The size a campaign starts from. A 2x2 board is over too quickly to count for much.
+const CAMPAIGN_START: usize = 3;
+
+fn main() -> ExitCode {
+  match run() {
+    Ok(()) => ExitCode::SUCCESS,
+    Err(err @ Error::Quiet) => err.exit_code(),
+    Err(err) => {
+      eprintln!("Error: {err}");
+      if let Error::Usage(_) = err {
+        eprintln!("Run with --help for usage.");
+      }
+      err.exit_code()
+    }
+  }
+}
+
+fn run() -> Result<(), Error> {
+  let (config, mut warnings) = Config::load();
+  let (defaults, default_warnings) = config.defaults();
+  warnings.extend(default_warnings);
+  let mut options = match parse_args(&defaults).map_err(Error::Usage)? {
+    Command::Play(options) => *options,
+    Command::Generate(options) => return Ok(generate::run(options)?),
+    Command::Bench(options) => return bench::run(options),
+  };
+  if let Some(code) = &options.check {
+    return check(code, options.with_length, options.within, options.repair);
+  }
+  if options.stats_reset {
+    return reset_stats(options.yes);
+  }
+
+  let (keys, key_warnings) = KeyBindings::load(options.keys, &config.keys);
+  let (themes, theme_warnings) = config.themes();
+  let (move_metric, metric_warnings) = config.move_metric();
+  let (goal, goal_warnings) = config.goal();
+  let (idle_attract, idle_warnings) = config.idle_attract();
+  let (move_cap, move_cap_warnings) = config.move_cap();
+  let (confirm_quit, confirm_warnings) = config.confirm_quit();
+  let (escape, escape_warnings) = config.escape();
+  let (win_screen, win_screen_warnings) = config.win_screen();
+  let (movement, movement_warnings) = config.movement();
+  warnings.extend(key_warnings);
+  warnings.extend(move_cap_warnings);
+  warnings.extend(theme_warnings);
+  warnings.extend(metric_warnings);
+  warnings.extend(goal_warnings);
+  warnings.extend(idle_warnings);
+  warnings.extend(confirm_warnings);
+  warnings.extend(escape_warnings);
+  warnings.extend(win_screen_warnings);
+  warnings.extend(movement_warnings);
+  let reference = match &options.reference_scores {
+    Some(path) => {
+      let (reference, reference_warnings) = BestMoves::reference(path)?;
+      warnings.extend(reference_warnings);
+      Some(reference)
+    }
+    None => None,
+  };
+  for warning in warnings {
+    eprintln!("Warning: {warning}");
+  }
+  if options.verbose {
+    let on_off = if options.color { "on" } else { "off" };
+    eprintln!("Colors {on_off}: {}", options.color_reason);
+  }
+
+  let goal_file = options
+    .goal_file
+    .clone()
+    .or_else(|| config.goal_file.as_ref().map(PathBuf::from));
+  let goal = match &goal_file {
+    Some(path) => read_goal(path)?,
+    None => goal,
   };
+  if let Some(size) = goal.size() {
+    if let Mode::Campaign(_) = options.mode {
+      return Err(Error::Usage(
+        "a custom goal only fits one size of board, so it can't be used in a campaign".to_string(),
+      ));
+    }
+    // A size asked for outright can't just be changed to fit.
+    let asked = match options.mode {
+      Mode::Demo | Mode::DemoStatic => Some(demo_grid().size()),
+      _ => options.size_given.then_some(options.size),
+    };
+    if let Some(other) = asked
+      && other != size
+    {
+      return Err(goal_size_error(size, other));
+    }
+    options.size = size;
+  }
 
+  let (mut grid, solution) = match options.mode {
+    Mode::Demo | Mode::DemoStatic => (demo_grid().relabeled(goal.clone()), None),
+    Mode::Attract | Mode::Standard => new_grid(
+      options.size,
+      &goal,
+      options.difficulty,
+      config.min_hard_moves,
+    ),
+    Mode::Tournament(_) => (
+      seeded_grid(options.size, &goal, options.difficulty, options.seed),
+      None,
+    ),
+    Mode::Daily(date) => (
+      seeded_grid(options.size, &goal, options.difficulty, date.seed()),
+      None,
+    ),
+    Mode::Campaign(_) => new_grid(
+      CAMPAIGN_START,
+      &goal,
+      options.difficulty,
+      config.min_hard_moves,
+    ),
+  };
+  // Kept with the board it solves, since moves or an import may change the board yet.
+  let shuffled = solution.map(|solution| (grid.clone(), solution));
+  let mut moves = options.moves.clone();
+  let mut moves_from = "--moves";
+  let mut repaired = None;
+  // What stopped an imported move list partway, if something did.
+  let mut cut_short = None;
+  if let Some(path) = &options.import {
+    let (list, problem) = MoveList::parse(&read_input(path)?)
+      .map_err(|err| Error::Usage(format!("{}: {err}", path.display())))?;
+    cut_short = problem.map(|problem| format!("{}: {problem}", path.display()));
+    if let Some(start) = list.start {
+      if let Some(size) = goal.size()
+        && size != start.size()
+      {
+        return Err(goal_size_error(size, start.size()));
+      }
+      grid = start.with_goal(goal.clone());
+      if !options.repair && !grid.is_solvable() {
+        return Err(Error::Failed(format!(
+          "{}: the start position can't be solved (--repair swaps two tiles so it can)",
+          path.display()
+        )));
+      }
+      repaired = grid.repair();
+      if let Some((a, b)) = repaired {
+        eprintln!("Swapped tiles {a} and {b} so the start position can be solved");
+      }
+    }
+    moves = list.moves;
+    moves_from = "--import";
+  }
+  for (i, &direction) in moves.iter().enumerate() {
+    if grid.slide(direction) {
+      continue;
+    }
+    let problem = format!(
+      "move {} in {moves_from} ({}) can't be made",
+      i + 1,
+      direction.name()
+    );
+    // A file might have been garbled partway through, so what came before is still
+    // worth playing. Moves typed on the command line can just be typed again.
+    if options.import.is_none() {
+      return Err(Error::Usage(problem));
+    }
+    moves.truncate(i);
+    cut_short = Some(problem);
+    break;
+  }
+  let cut_short = cut_short.map(|problem| {
+    format!(
+      "{problem}, so only the {} moves before it were played",
+      moves.len()
+    )
+  });
+  if let Some(problem) = &cut_short {
+    eprintln!("{problem}");
+  }
+  // Start with the theme picked last time, if it's still one to pick from.
+  let mut themes = VecDeque::from(themes);
+  if let Some(i) = themes
+    .iter()
+    .position(|theme| Some(theme.name) == options.theme.as_deref())
+  {
+    themes.rotate_left(i);
+  }
+  if let Some(scheme) = options.color_scheme {
+    for theme in &mut themes {
+      theme.color_scheme = scheme;
+    }
+  }
+  let theme = themes.pop_front().expect("there's always a theme");
+  let padding = options.padding.or(config.padding).unwrap_or(0);
+  let win_screen = options.win_screen.unwrap_or(win_screen);
+  if options.print_config {
+    // Fill in everything that's been worked out, so the file gives the same game
+    // without the settings, environment or flags.
+    let effective = Config {
+      size: Some(options.size),
+      difficulty: Some(options.difficulty.id().to_lowercase()),
+      key_preset: Some(options.keys.name().to_string()),
+      align: Some(options.align.name().to_string()),
+      keys: keys.config_table(),
+      themes: Some(
+        iter::once(&theme)
+          .chain(&themes)
+          .map(|theme| theme.name.to_string())
+          .collect(),
+      ),
+      tile_color_scheme: Some(theme.color_scheme.name().to_string()),
+      move_metric: Some(move_metric.name().to_string()),
+      goal: match goal {
+        Goal::Custom(_) => config.goal.clone(),
+        _ => Some(goal.name().to_string()),
+      },
+      goal_file: goal_file.map(|path| path.display().to_string()),
+      confirm_quit: Some(confirm_quit.name().to_string()),
+      escape: Some(escape.name().to_string()),
+      win_screen: Some(win_screen.name().to_string()),
+      movement: Some(movement.name().to_string()),
+      padding: Some(padding),
+      idle_attract_seconds: idle_attract.map(|idle| idle.as_secs()),
+      max_moves_per_second: move_cap.map(|cap| 1.0 / cap.as_secs_f64()),
+      ..config
+    };
+    print!("{}", toml::to_string(&effective).map_err(io::Error::other)?);
+    return Ok(());
+  }
+  if let Some(path) = &options.snapshot {
+    std::fs::write(path, snapshot::svg(&grid, &theme, options.color))?;
+    println!("Saved the board to {}", path.display());
+    return Ok(());
+  }
+  if options.exit_after {
+    print!("{grid}");
+    if !grid.is_solved() {
+      return Err(Error::Failed("the board isn't solved".to_string()));
+    }
+    return Ok(());
+  }
+
+  let set_title = options.set_title;
+  if set_title {
+    title::save()?;
+    title::restore_on_panic();
+  }
+  let focus_pause = options.focus_pause;
+  let mouse = options.reveal_on_hover;
   let mut terminal = ratatui::init();
-  let mut app = App::new(mode);
-  let result = app.run(&mut terminal);
+  // Have pastes come as a whole, rather than as keys that would play moves.
+  execute!(io::stdout(), EnableBracketedPaste)?;
+  if focus_pause {
+    execute!(io::stdout(), EnableFocusChange)?;
+  }
+  if mouse {
+    execute!(io::stdout(), EnableMouseCapture)?;
+  }
+  // Ask for key releases, so a peek at the goals ends as soon as the key is let go.
+  let key_releases = terminal::supports_keyboard_enhancement().unwrap_or(false);
+  if key_releases {
+    execute!(
+      io::stdout(),
+      PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES)
+    )?;
+  }
+  let mut settings = Settings {
+    size: Some(options.size),
+    difficulty: None,
+    keys: Some(options.keys.name().to_string()),
+    align: Some(options.align.name().to_string()),
+    theme: None,
+  };
+  let mut app = App::new(
+    options,
+    grid,
+    keys,
+    theme,
+    move_metric,
+    config.min_hard_moves,
+    idle_attract,
+  );
+  if let Some((grid, solution)) = shuffled {
+    app.keep_start_solution(grid, solution);
+  }
+  app.reference = reference;
+  app.confirm_quit = confirm_quit;
+  app.escape = escape;
+  app.themes = themes;
+  app.padding = padding;
+  app.win_screen = win_screen;
+  app.movement = movement;
+  app.show_instructions = !config.hide_instructions;
+  app.show_idle = config.show_idle;
+  app.set_move_cap(move_cap);
+  if let Some((a, b)) = repaired {
+    app.message = Some(format!(
+      "Swapped tiles {a} and {b} so the puzzle can be solved"
+    ));
+  }
+  if cut_short.is_some() {
+    app.message = cut_short;
+  }
+  let mut result = app.run(&mut terminal);
+  result = result.and(execute!(io::stdout(), DisableBracketedPaste));
+  if key_releases {
+    result = result.and(execute!(io::stdout(), PopKeyboardEnhancementFlags));
+  }
+  if focus_pause {
+    result = result.and(execute!(io::stdout(), DisableFocusChange));
+  }
+  if mouse {
+    result = result.and(execute!(io::stdout(), DisableMouseCapture));
+  }
   ratatui::restore();
-  result
+  if set_title {
+    title::restore()?;
+  }
+  result?;
+  // Remember the difficulty the player ended on, even if they changed it in game.
+  settings.difficulty = Some(app.difficulty.id().to_lowercase());
+  settings.theme = Some(app.theme.name.to_string());
+  if let Err(err) = settings.save() {
+    eprintln!("Warning: couldn't save settings: {err}");
+  }
+  Ok(())
+}
+
+/// Read a file named on the command line, where "-" means standard input. The game can
+/// still be played afterwards, since keys are read from the terminal itself.
+fn read_input(path: &Path) -> io::Result<String> {
+  let mut bytes = Vec::new();
+  if path == Path::new("-") {
+    io::stdin().read_to_end(&mut bytes)?;
+  } else {
+    bytes = std::fs::read(path)?;
+  }
+  // A garbled file can have bytes that aren't text. They're kept as � rather than
+  // failing the whole read, so the problem can be pointed out where it is.
+  Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// The error for a custom goal that doesn't fit the board it would be used on.
+fn goal_size_error(size: usize, other: usize) -> Error {
+  Error::Usage(format!(
+    "the custom goal is for a {size}x{size} board, not {other}x{other}"
+  ))
+}
+
+/// Read a custom goal from a file holding its position code, on the first line that
+/// isn't blank or a comment starting with "#". A path of "-" reads standard input.
+fn read_goal(path: &Path) -> Result<Goal, Error> {
+  let contents = read_input(path)?;
+  let code = contents
+    .lines()
+    .map(str::trim)
+    .find(|line| !line.is_empty() && !line.starts_with('#'))
+    .unwrap_or_default();
+  Goal::custom(code).map_err(|err| Error::Usage(format!("{}: {err}", path.display())))
+}
+
+/// Print whether the position with the given code can be solved, and how many moves it
+/// takes if asked, without starting the game. An unsolvable position fails quietly, so
+/// scripts can go by the exit code. A code of "-" is read from standard input. With
+/// `repair`, an unsolvable position is fixed up first, and the fixed code printed. With
+/// `within`, a position that can't be solved in that many moves fails the same way.
+fn check(code: &str, with_length: bool, within: Option<usize>, repair: bool) -> Result<(), Error> {
+  let input;
+  let code = if code == "-" {
+    input = read_input(Path::new(code))?;
+    input.trim()
+  } else {
+    code
+  };
+  let mut grid = Grid::from_code(code).map_err(|err| Error::Usage(format!("--check: {err}")))?;
+  if repair && let Some((a, b)) = grid.repair() {
+    println!("repaired {} by swapping {a} and {b}", grid.code());
+  }
+  if !grid.is_solvable() {
+    println!("unsolvable");
+    return Err(Error::Quiet);
+  }
+  if let Some(budget) = within {
+    let Some(solution) = solver::solve_within(&grid, budget) else {
+      println!("not within {budget}");
+      return Err(Error::Quiet);
+    };
+    println!("solvable {}", solution.len());
+  } else if with_length {
+    let solution = solver::solve(&grid).expect("The position is solvable.");
+    println!("solvable {}", solution.len());
+  } else {
+    println!("solvable");
+  }
+  Ok(())
+}
+
+/// Delete the saved records and settings, after asking unless `yes` is set. Only the
+/// files the game itself writes are touched.
+fn reset_stats(yes: bool) -> Result<(), Error> {
+  let Some(dir) = data_dir() else {
+    return Err(Error::Failed(
+      "there's no data directory, since HOME isn't set".to_string(),
+    ));
+  };
+  let files: Vec<_> = STATS_FILES
+    .into_iter()
+    .chain([SETTINGS_FILE])
+    .map(|name| dir.join(name))
+    .filter(|path| path.exists())
+    .collect();
+  if files.is_empty() {
+    println!("Nothing to clear in {}", dir.display());
+    return Ok(());
+  }
+  println!("This deletes:");
+  for path in &files {
+    println!("  {}", path.display());
+  }
+  if !yes {
+    print!("Go ahead? [y/N] ");
+    io::Write::flush(&mut io::stdout())?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    if !matches!(answer.trim(), "y" | "Y" | "yes") {
+      println!("Nothing was deleted");
+      return Ok(());
+    }
+  }
+  for path in &files {
+    std::fs::remove_file(path)?;
+    println!("Deleted {}", path.display());
+  }
+  Ok(())
+}
+
+/// What to do, as chosen on the command line.
+enum Command {
+  /// Play the game.
+  Play(Box<Options>),
+  /// Print puzzles instead of playing.
+  Generate(GenerateOptions),
+  /// Time the solver.
+  Bench(BenchOptions),
 }
 
-/// For now, the only arg is --demo which gives a specific shuffling.
-/// Return true for demo mode.
-fn parse_args() -> bool {
-  let args: Vec<_> = std::env::args().collect();
-  return args.len() > 1 && args[1] == "--demo";
+/// Settings chosen on the command line.
+pub struct Options {
+  mode: Mode,
+  /// The number of tiles on each side of the board.
+  size: usize,
+  /// Whether the size came from --size, rather than the settings or config.
+  size_given: bool,
+  difficulty: Difficulty,
+  /// The seed for the first puzzle of a tournament. The rest follow on from it.
+  seed: u64,
+  /// How many undos the player gets, if they're limited.
+  undo_limit: Option<u32>,
+  /// Whether to flash the edge of the board when a move can't be made.
+  flash_illegal_moves: bool,
+  /// Whether to point out moves that take the board further from solved.
+  coach: bool,
+  /// Whether to race the best run on positions solved before.
+  ghost: bool,
+  /// Whether to count down the fewest moves left while the player follows them.
+  track_optimal: bool,
+  /// Whether to lock tiles as soon as they're in place, instead of waiting for the lock
+  /// key.
+  auto_lock: bool,
+  /// Whether the finish row key can fill in a row that's one tile short.
+  row_assist: bool,
+  /// Whether to suggest another difficulty when the recent solves make it look too easy
+  /// or too hard.
+  advise: bool,
+  /// Whether tiles move smoothly, or jump straight to where they end up.
+  animate: bool,
+  /// Whether to use colors at all. Detected from the terminal unless --color or
+  /// --no-color says otherwise.
+  color: bool,
+  /// Why colors are on or off, for --verbose.
+  color_reason: String,
+  /// The key bindings to start from, before any environment overrides.
+  keys: Preset,
+  /// Where the board goes across the terminal.
+  align: Align,
+  /// Whether to print details about the setup before starting.
+  verbose: bool,
+  /// Whether to show the game's status in the terminal's title. Some terminal
+  /// multiplexers don't handle title changes well.
+  set_title: bool,
+  /// Whether to stop the clock while the terminal is in the background. Terminals that
+  /// don't report focus changes just never pause.
+  focus_pause: bool,
+  /// Whether to hide the numbers on the tiles, except under the mouse.
+  reveal_on_hover: bool,
+  /// Moves to make on the starting board before play begins.
+  moves: Vec<Direction>,
+  /// A saved move list to play instead of --moves, from its own start position if it
+  /// has one.
+  import: Option<PathBuf>,
+  /// A file with a custom goal's position code, over the config's goal.
+  goal_file: Option<PathBuf>,
+  /// Whether to print the board after the scripted moves and exit instead of playing.
+  exit_after: bool,
+  /// A position code to check for solvability instead of playing.
+  check: Option<String>,
+  /// Whether to fix up an unsolvable position from --import or --check by swapping two
+  /// tiles, instead of rejecting it.
+  repair: bool,
+  /// Whether --check also prints how many moves the position takes.
+  with_length: bool,
+  /// The most moves --check allows for solving the position, if it's limited.
+  within: Option<usize>,
+  /// Whether to delete the saved records and settings instead of playing.
+  stats_reset: bool,
+  /// Whether --stats-reset goes ahead without asking.
+  yes: bool,
+  /// Someone else's best moves to show alongside the player's own.
+  reference_scores: Option<PathBuf>,
+  /// A file to watch for moves written by another program, to play them as they come.
+  spectate: Option<PathBuf>,
+  /// The name of the theme picked last time, from the settings.
+  theme: Option<String>,
+  /// How to color tiles, overriding the config file.
+  color_scheme: Option<TileColorScheme>,
+  /// Space around the whole game, overriding the config file.
+  padding: Option<u16>,
+  /// What to show on a win, overriding the config file.
+  win_screen: Option<WinScreen>,
+  /// Where to save a picture of the board instead of playing.
+  snapshot: Option<PathBuf>,
+  /// Whether to print the settings in effect as a config file instead of playing.
+  print_config: bool,
+}
+
+impl Options {
+  /// Every option left as it is without flags, starting from the given defaults.
+  fn new(defaults: &Defaults) -> Options {
+    Options {
+      mode: Mode::Standard,
+      size: defaults.size,
+      size_given: false,
+      difficulty: defaults.difficulty,
+      seed: 0,
+      undo_limit: None,
+      flash_illegal_moves: false,
+      coach: false,
+      ghost: false,
+      track_optimal: false,
+      auto_lock: false,
+      row_assist: false,
+      advise: true,
+      animate: true,
+      color: true,
+      color_reason: String::new(),
+      keys: defaults.keys,
+      align: defaults.align,
+      verbose: false,
+      set_title: true,
+      focus_pause: true,
+      reveal_on_hover: false,
+      moves: Vec::new(),
+      import: None,
+      goal_file: None,
+      exit_after: false,
+      check: None,
+      with_length: false,
+      within: None,
+      repair: false,
+      stats_reset: false,
+      yes: false,
+      reference_scores: None,
+      spectate: None,
+      theme: None,
+      color_scheme: None,
+      win_screen: None,
+      padding: None,
+      snapshot: None,
+      print_config: false,
+    }
+  }
 }
 
 enum Mode {
+  /// Solve the demo board, then scramble and solve it again over and over, until a key
+  /// is pressed.
   Demo,
+  /// Just start from the demo board.
+  DemoStatic,
+  /// The board makes random moves by itself, like a screensaver.
+  Attract,
   Standard,
+  /// Solve this many seeded puzzles in a row, for a combined score.
+  Tournament(usize),
+  /// Solve one puzzle of each size from the smallest up to this one, for a combined
+  /// score.
+  Campaign(usize),
+  /// Solve the puzzle for this date, which is the same for everyone.
+  Daily(Date),
 }
 
-type Cell = Option<u8>;
-type Row = [Cell; 4];
-type Grid = [Row; 4];
+/// Where the board goes across the terminal.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Align {
+  /// A fixed distance from the left edge, which suits tiling window managers.
+  Left,
+  /// In the middle of the terminal.
+  Center,
+}
 
-/// Create a new randomly shuffled grid.
-fn new_grid() -> Grid {
-  let mut numbers: [u8; 15] = array::from_fn(|i| (i + 1) as u8);
+impl Align {
+  fn name(self) -> &'static str {
+    match self {
+      Align::Left => "left",
+      Align::Center => "center",
+    }
+  }
+}
 
-  let mut rng = rng();
+impl FromStr for Align {
+  type Err = ();
 
-  // If you just shuffle the array, there's a 50% chance the puzzle is unsolvable.
-  // Instead, do an even number of exchanges. According to
-  // https://en.wikipedia.org/wiki/15_puzzle#Solvability this should produce a
-  // solvable arrangement. For our even number, use 50 which should be high enough.
-  let mut swaps = 50;
-  while swaps > 0 {
-    let a = rng.random_range(0..15);
-    let b = rng.random_range(0..15);
-    if a == b {
-      continue;
+  fn from_str(name: &str) -> Result<Self, ()> {
+    match name {
+      "left" => Ok(Align::Left),
+      "center" => Ok(Align::Center),
+      _ => Err(()),
+    }
+  }
+}
+
+/// How thoroughly new puzzles are shuffled.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Difficulty {
+  /// Just this many random moves from solved, so it never takes more than that.
+  Warmup(u32),
+  Easy,
+  Medium,
+  Hard,
+}
+
+impl FromStr for Difficulty {
+  type Err = ();
+
+  fn from_str(name: &str) -> Result<Self, ()> {
+    match name {
+      "easy" => Ok(Difficulty::Easy),
+      "medium" => Ok(Difficulty::Medium),
+      "hard" => Ok(Difficulty::Hard),
+      _ => Err(()),
     }
+  }
+}
 
-    numbers.swap(a, b);
-    swaps -= 1;
+impl Difficulty {
+  fn name(self) -> &'static str {
+    match self {
+      Difficulty::Warmup(_) => "Warmup",
+      Difficulty::Easy => "Easy",
+      Difficulty::Medium => "Medium",
+      Difficulty::Hard => "Hard",
+    }
   }
 
-  let cells: [Cell; 16] = array::from_fn(|n| match n {
-    15 => None,
-    n => Some(numbers[n]),
-  });
+  /// A name that tells apart every way of shuffling, to keep records under.
+  fn id(self) -> String {
+    match self {
+      Difficulty::Warmup(moves) => format!("{}{moves}", self.name()),
+      _ => self.name().to_string(),
+    }
+  }
+
+  /// The difficulty with the given id, in lowercase, as saved in the settings.
+  fn from_id(id: &str) -> Option<Difficulty> {
+    match id.strip_prefix("warmup") {
+      Some(moves) => moves
+        .parse()
+        .ok()
+        .filter(|&moves| moves > 0)
+        .map(Difficulty::Warmup),
+      None => id.parse().ok(),
+    }
+  }
 
-  [
-    <Row>::try_from(&cells[0..4]).unwrap(),
-    <Row>::try_from(&cells[4..8]).unwrap(),
-    <Row>::try_from(&cells[8..12]).unwrap(),
-    <Row>::try_from(&cells[12..16]).unwrap(),
-  ]
+  /// The color of the difficulty's badge in the header.
+  fn color(self) -> Color {
+    match self {
+      Difficulty::Warmup(_) => Color::Cyan,
+      Difficulty::Easy => Color::Green,
+      Difficulty::Medium => Color::Yellow,
+      Difficulty::Hard => Color::Red,
+    }
+  }
+
+  /// The next difficulty up, or this one if it's already the hardest.
+  fn harder(self) -> Difficulty {
+    match self {
+      Difficulty::Warmup(_) => Difficulty::Easy,
+      Difficulty::Easy => Difficulty::Medium,
+      Difficulty::Medium | Difficulty::Hard => Difficulty::Hard,
+    }
+  }
+
+  /// The next difficulty down, or this one if it's already the easiest.
+  fn easier(self) -> Difficulty {
+    match self {
+      Difficulty::Warmup(_) | Difficulty::Easy => self,
+      Difficulty::Medium => Difficulty::Easy,
+      Difficulty::Hard => Difficulty::Medium,
+    }
+  }
+
+  /// Roughly how many moves a player who's comfortable at this difficulty takes on a
+  /// board of this size: twice the shuffle for the easier levels, and for a full shuffle,
+  /// twice what the solver typically needs.
+  fn expected_moves(self, size: usize) -> u32 {
+    match self {
+      Difficulty::Warmup(moves) => 2 * moves,
+      Difficulty::Easy => 20,
+      Difficulty::Medium => 80,
+      Difficulty::Hard => 2 * (size as u32).pow(3),
+    }
+  }
+
+  /// The difficulty to suggest after the given solves at this one, oldest first, if the
+  /// latest few were all well under the expected moves, or all well over.
+  fn advice(self, size: usize, moves: &[u32]) -> Option<Difficulty> {
+    if moves.len() < ADVICE_SOLVES {
+      return None;
+    }
+    let expected = self.expected_moves(size);
+    let latest = &moves[moves.len() - ADVICE_SOLVES..];
+    let suggestion = if latest.iter().all(|&moves| 2 * moves < expected) {
+      self.harder()
+    } else if latest.iter().all(|&moves| moves > 2 * expected) {
+      self.easier()
+    } else {
+      return None;
+    };
+    (suggestion != self).then_some(suggestion)
+  }
+}
+
+/// Create a new randomly shuffled grid. Hard grids are reshuffled until solving them
+/// takes at least `min_hard_moves`, if that's set, giving up after a few tries in case it
+/// can't be done on a board this small. The solution found along the way comes too, so
+/// it doesn't have to be worked out again.
+fn new_grid(
+  size: usize,
+  goal: &Goal,
+  difficulty: Difficulty,
+  min_hard_moves: Option<usize>,
+) -> (Grid, Option<Vec<Direction>>) {
+  let mut rng = rng();
+  let mut grid = new_grid_for(size, goal, difficulty, &mut rng);
+  let (Difficulty::Hard, Some(min)) = (difficulty, min_hard_moves) else {
+    return (grid, None);
+  };
+  let mut solution = solver::solve(&grid).unwrap_or_default();
+  for _ in 1..MAX_SHUFFLES {
+    if solution.len() >= min {
+      break;
+    }
+    grid = new_grid_for(size, goal, difficulty, &mut rng);
+    solution = solver::solve(&grid).unwrap_or_default();
+  }
+  (grid, Some(solution))
+}
+
+/// The puzzle for the given seed. It's the same one generate prints first for that seed.
+fn seeded_grid(size: usize, goal: &Goal, difficulty: Difficulty, seed: u64) -> Grid {
+  new_grid_for(size, goal, difficulty, &mut StdRng::seed_from_u64(seed))
+}
+
+/// Create a new grid shuffled as much as the difficulty calls for, using the given
+/// source of randomness.
+fn new_grid_for(size: usize, goal: &Goal, difficulty: Difficulty, rng: &mut impl Rng) -> Grid {
+  let goal = goal.clone();
+  match difficulty {
+    Difficulty::Warmup(moves) => scramble_grid(size, goal, rng, moves),
+    Difficulty::Easy => scramble_grid(size, goal, rng, 10),
+    Difficulty::Medium => scramble_grid(size, goal, rng, 40),
+    Difficulty::Hard => new_grid_with(size, goal, rng),
+  }
 }
 
 // Create a grid with a specific shuffling.
 fn demo_grid() -> Grid {
-  [
+  Grid::from_rows([
     [Some(1), Some(2), Some(3), Some(4)],
     [Some(5), Some(6), Some(7), Some(8)],
     [Some(11), Some(12), Some(13), Some(15)],
     [Some(10), Some(9), None, Some(14)],
-  ]
-}
-
-struct App {
-  grid: Grid,
-  exit: bool,
+  ])
 }
 
-impl App {
-  fn new(mode: Mode) -> Self {
-    let grid = match mode {
-      Mode::Demo => demo_grid(),
-      Mode::Standard => new_grid(),
-    };
-    App { grid, exit: false }
-  }
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::grid::{MAX_SIZE, MIN_SIZE};
 
-  /// Replace the grid with a new randomly shuffled grid.
-  fn shuffle(&mut self) {
-    self.grid = new_grid();
+  /// The demo grid is written out by hand, so check it can still be solved, or the demo
+  /// would never finish.
+  #[test]
+  fn demo_grid_can_be_solved() {
+    let grid = demo_grid();
+    assert!(grid.is_solvable());
+    assert!(solver::solve(&grid).is_some());
   }
 
-  /// Check if the puzzle is in a winning state.
-  fn is_win(&self) -> bool {
-    let arr = <[Cell; 16]>::try_from(self.grid.concat()).unwrap();
-    for i in 0..15 {
-      if arr[i] != Some(i as u8 + 1) {
-        return false;
+  #[test]
+  fn warmups_take_no_more_moves_than_asked() {
+    for size in MIN_SIZE..=solver::MAX_OPTIMAL_SIZE {
+      for moves in 1..=12 {
+        for seed in 0..10 {
+          let grid = new_grid_for(
+            size,
+            &Goal::Standard,
+            Difficulty::Warmup(moves),
+            &mut StdRng::seed_from_u64(seed),
+          );
+          let solution = solver::solve(&grid).unwrap();
+          assert!(
+            !solution.is_empty() && solution.len() <= moves as usize,
+            "a {moves}-move warmup took {} moves:\n{grid}",
+            solution.len()
+          );
+        }
       }
     }
-    true
   }
 
-  /// Make a move if possible. If the given direction doesn't work, do nothing.
-  fn make_move(&mut self, (x, y): (i8, i8)) {
-    let (blank_x, blank_y) = self.find_blank();
-    let (tile_x, tile_y) = (blank_x + x, blank_y + y);
-    if tile_x < 0 || tile_x >= 4 || tile_y < 0 || tile_y >= 4 {
-      // Illegal move; just ignore it.
-      return;
+  #[test]
+  fn hard_boards_take_at_least_the_minimum_moves() {
+    for _ in 0..5 {
+      let (grid, solution) = new_grid(3, &Goal::Standard, Difficulty::Hard, Some(24));
+      let solution = solution.expect("hard boards with a minimum are solved");
+      assert!(solution.len() >= 24, "{} moves:\n{grid}", solution.len());
+      // It's the fewest moves, not just any solution that's long enough.
+      assert_eq!(solver::solve(&grid).unwrap().len(), solution.len());
     }
-    let tile = self.grid[tile_y as usize][tile_x as usize];
-    self.grid[blank_y as usize][blank_x as usize] = tile;
-    self.grid[tile_y as usize][tile_x as usize] = None;
+    let (_, solution) = new_grid(3, &Goal::Standard, Difficulty::Hard, None);
+    assert_eq!(solution, None);
   }
 
-  /// Returns the location of the blank square.
-  fn find_blank(&self) -> (i8, i8) {
-    for x in 0..4 {
-      for y in 0..4 {
-        if self.grid[y][x].is_none() {
-          return (x as i8, y as i8);
-        }
-      }
-    }
-    unreachable!("There will always be a None in the grid somewhere.");
-  }
-
-  fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
-    while !self.exit {
-      terminal.draw(|frame| self.draw(frame))?;
-      self.handle_input()?;
-    }
-    Ok(())
-  }
-
-  fn draw(&self, frame: &mut Frame) {
-    frame.render_widget(self, frame.area());
-  }
-
-  fn handle_input(&mut self) -> io::Result<()> {
-    match event::read()? {
-      Event::Key(event) => match event.kind {
-        KeyEventKind::Press => match event.code {
-          KeyCode::Char('q') => {
-            self.exit = true;
-          }
-          KeyCode::Char('r') => {
-            self.shuffle();
-          }
-          KeyCode::Up | KeyCode::Char('w') => {
-            self.make_move((0, 1));
-          }
-          KeyCode::Down | KeyCode::Char('s') => {
-            self.make_move((0, -1));
-          }
-          KeyCode::Left | KeyCode::Char('a') => {
-            self.make_move((1, 0));
-          }
-          KeyCode::Right | KeyCode::Char('d') => {
-            self.make_move((-1, 0));
-          }
-          _ => {}
-        },
-        _ => {}
-      },
-      _ => {}
-    };
-    Ok(())
+  #[test]
+  fn advice_needs_enough_solves() {
+    let fast = [10; ADVICE_SOLVES];
+    assert_eq!(Difficulty::Medium.advice(4, &fast[1..]), None);
+    assert_eq!(Difficulty::Medium.advice(4, &fast), Some(Difficulty::Hard));
   }
-}
-
-impl Widget for &App {
-  fn render(self, area: Rect, buf: &mut Buffer) {
-    let vertical_layout = Layout::vertical([
-      Constraint::Length(2),
-      Constraint::Length(1),
-      Constraint::Percentage(100),
-    ]);
-    let [title_area, instructions_area, main_area] = vertical_layout.areas(area);
 
-    Line::from("Sliding Puzzle").bold().render(title_area, buf);
-    Line::from("        Instructions: Arrows or WASD to move. R to restart. Q to quit.")
-      .render(instructions_area, buf);
+  #[test]
+  fn advice_goes_by_the_latest_solves_only() {
+    let mut moves = vec![500];
+    moves.extend([39; ADVICE_SOLVES]);
+    assert_eq!(Difficulty::Medium.advice(4, &moves), Some(Difficulty::Hard));
+    // One solve that isn't well under is enough to hold off.
+    *moves.last_mut().unwrap() = 40;
+    assert_eq!(Difficulty::Medium.advice(4, &moves), None);
+  }
 
-    let puzzle_area = Rect {
-      x: main_area.x + 6,
-      y: main_area.y + 2,
-      width: TILE_WIDTH * 4 + 6,
-      height: TILE_HEIGHT * 4 + 2,
-    };
+  #[test]
+  fn advice_starts_at_half_and_twice_the_expected_moves() {
+    // Each difficulty with its expected moves on a 4x4 board, and where it goes from
+    // there.
+    let levels = [
+      (Difficulty::Warmup(5), 10, Some(Difficulty::Easy), None),
+      (Difficulty::Easy, 20, Some(Difficulty::Medium), None),
+      (
+        Difficulty::Medium,
+        80,
+        Some(Difficulty::Hard),
+        Some(Difficulty::Easy),
+      ),
+      (Difficulty::Hard, 128, None, Some(Difficulty::Medium)),
+    ];
+    for (difficulty, expected, harder, easier) in levels {
+      let advice = |moves: u32| difficulty.advice(4, &[moves; ADVICE_SOLVES]);
+      assert_eq!(advice(expected / 2 - 1), harder, "{difficulty:?}");
+      assert_eq!(advice(expected / 2), None, "{difficulty:?}");
+      assert_eq!(advice(expected), None, "{difficulty:?}");
+      assert_eq!(advice(2 * expected), None, "{difficulty:?}");
+      assert_eq!(advice(2 * expected + 1), easier, "{difficulty:?}");
+    }
+  }
 
-    let puzzle_border_color = if self.is_win() {
-      Color::Green
-    } else {
-      Color::Red
-    };
+  #[test]
+  fn hard_advice_scales_with_the_board() {
+    let advice = |size, moves| Difficulty::Hard.advice(size, &[moves; ADVICE_SOLVES]);
+    assert_eq!(advice(3, 109), Some(Difficulty::Medium));
+    assert_eq!(advice(4, 109), None);
+    assert_eq!(advice(4, 257), Some(Difficulty::Medium));
+  }
 
-    let puzzle_block = Block::bordered()
-      .border_style(Style::default().fg(puzzle_border_color))
-      .border_set(border::THICK);
-    puzzle_block.render(puzzle_area, buf);
+  #[test]
+  fn two_by_two_boards_start_scrambled_and_solvable() {
+    for difficulty in [
+      Difficulty::Warmup(1),
+      Difficulty::Warmup(12),
+      Difficulty::Easy,
+      Difficulty::Medium,
+      Difficulty::Hard,
+    ] {
+      for seed in 0..50 {
+        let grid = new_grid_for(
+          2,
+          &Goal::Standard,
+          difficulty,
+          &mut StdRng::seed_from_u64(seed),
+        );
+        assert!(grid.is_solvable(), "{difficulty:?} seed {seed}:\n{grid}");
+        assert!(!grid.is_solved(), "{difficulty:?} seed {seed}:\n{grid}");
+      }
+    }
+  }
 
-    let mut area = Rect {
-      x: puzzle_area.x + 3,
-      y: puzzle_area.y + 1,
-      width: TILE_WIDTH,
-      height: TILE_HEIGHT,
-    };
-    for row in self.grid {
-      for number in row {
-        match number {
-          Some(n) => {
-            let color = if n % 2 == 0 { Color::Gray } else { Color::Blue };
-            let block = Block::bordered().style(Style::default().fg(color));
-            let text_area = block.inner(area);
-            block.render(area, buf);
-            Line::from(format!(" {:02}", n)).render(text_area, buf);
-          }
-          None => {}
+  #[test]
+  fn seeded_grids_repeat_and_can_be_solved() {
+    for size in MIN_SIZE..=MAX_SIZE {
+      for difficulty in [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard] {
+        for seed in 0..20 {
+          let grid = new_grid_for(
+            size,
+            &Goal::Standard,
+            difficulty,
+            &mut StdRng::seed_from_u64(seed),
+          );
+          let again = new_grid_for(
+            size,
+            &Goal::Standard,
+            difficulty,
+            &mut StdRng::seed_from_u64(seed),
+          );
+          assert_eq!(grid, again, "seed {seed} gave two grids");
+          assert!(
+            grid.is_solvable(),
+            "seed {seed} gave an unsolvable grid:\n{grid}"
+          );
         }
-
-        area.x += TILE_WIDTH;
       }
-      area.x = puzzle_area.x + 3;
-      area.y += TILE_HEIGHT;
     }
   }
 }
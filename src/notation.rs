@@ -0,0 +1,181 @@
+use crate::grid::{Direction, Grid};
+
+/// How many move letters to put on each line of a saved move list.
+const MOVES_PER_LINE: usize = 60;
+
+/// A game written down as text, so it can be shared with other sliding puzzle tools.
+///
+/// Each move is one letter, U, D, L or R, naming the direction the tile slides. The
+/// blank goes the opposite way, so "U" slides the tile below the blank up into it. Case
+/// and whitespace don't matter. A line starting with "start" gives the position code
+/// the moves were made from, and lines starting with "#" are comments.
+pub struct MoveList {
+  /// The position the moves start from, if the list says.
+  pub start: Option<Grid>,
+  pub moves: Vec<Direction>,
+}
+
+impl MoveList {
+  /// Read a move list back from its text. A problem with the start position fails the
+  /// whole list, since the moves can't be played without it. A problem with the moves,
+  /// like a file that was only partly written, keeps the moves before it: the list comes
+  /// back along with what went wrong. A list with nothing to play at all, not even a
+  /// start position, fails too.
+  pub fn parse(text: &str) -> Result<(MoveList, Option<String>), String> {
+    let (list, problem) = MoveList::parse_lines(text)?;
+    if list.start.is_none() && list.moves.is_empty() {
+      return Err(problem.unwrap_or_else(|| "there are no moves in it".to_string()));
+    }
+    Ok((list, problem))
+  }
+
+  /// Read as much of a move list as makes sense, with what stopped it partway, if
+  /// anything did.
+  fn parse_lines(text: &str) -> Result<(MoveList, Option<String>), String> {
+    let mut list = MoveList {
+      start: None,
+      moves: Vec::new(),
+    };
+    for (i, line) in text.lines().enumerate() {
+      let line = line.trim();
+      if line.starts_with('#') {
+        continue;
+      }
+      if let Some(code) = line.strip_prefix("start") {
+        let at = |problem: &str| format!("line {}: {problem}", i + 1);
+        if list.start.is_some() {
+          return Err(at("there's a second start position"));
+        }
+        if !list.moves.is_empty() {
+          return Err(at("the start position comes after some of the moves"));
+        }
+        let grid =
+          Grid::from_code(code.trim()).map_err(|err| at(&format!("bad start position: {err}")))?;
+        list.start = Some(grid);
+        continue;
+      }
+      let letters: Vec<char> = line.chars().filter(|c| !c.is_whitespace()).collect();
+      for letter in letters {
+        let Some(direction) = Direction::from_letter(letter) else {
+          let problem = format!(
+            "line {}: unknown move {letter:?} (expected u, d, l or r)",
+            i + 1
+          );
+          return Ok((list, Some(problem)));
+        };
+        list.moves.push(direction);
+      }
+    }
+    Ok((list, None))
+  }
+
+  /// The text for this move list, which `parse` reads back.
+  pub fn text(&self) -> String {
+    // Spelled out, since players who move the blank would read the letters the other
+    // way round.
+    let mut text = "# Each letter is the direction a tile slides: U, D, L or R.\n\
+                    # The blank goes the opposite way.\n"
+      .to_string();
+    if let Some(start) = &self.start {
+      text.push_str(&format!("start {}\n", start.code()));
+    }
+    for line in self.moves.chunks(MOVES_PER_LINE) {
+      text.extend(line.iter().map(|direction| direction.letter()));
+      text.push('\n');
+    }
+    text
+  }
+}
+
+/// Parse a run of move letters, skipping whitespace. On failure, returns the first
+/// character that isn't a move.
+pub fn parse_moves(text: &str) -> Result<Vec<Direction>, char> {
+  text
+    .chars()
+    .filter(|c| !c.is_whitespace())
+    .map(|letter| Direction::from_letter(letter).ok_or(letter))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use rand::SeedableRng;
+  use rand::rngs::StdRng;
+
+  use super::*;
+  use crate::grid::{Goal, new_grid_with, random_move};
+
+  /// The grid after playing the list's moves from its start.
+  fn play(list: &MoveList) -> Grid {
+    let mut grid = list.start.clone().unwrap();
+    for &direction in &list.moves {
+      assert!(grid.slide(direction));
+    }
+    grid
+  }
+
+  #[test]
+  fn exported_moves_import_to_the_same_board() {
+    let mut rng = StdRng::seed_from_u64(145);
+    let start = new_grid_with(4, Goal::Standard, &mut rng);
+    let mut grid = start.clone();
+    let mut moves = Vec::new();
+    // Enough to run over more than one line.
+    for _ in 0..150 {
+      let direction = random_move(&grid, moves.last().copied(), &mut rng);
+      grid.slide(direction);
+      moves.push(direction);
+    }
+    let list = MoveList {
+      start: Some(start),
+      moves,
+    };
+
+    let (imported, problem) = MoveList::parse(&list.text()).unwrap();
+    assert_eq!(problem, None);
+    assert_eq!(imported.start, list.start);
+    assert_eq!(imported.moves, list.moves);
+    assert_eq!(play(&imported), grid);
+  }
+
+  #[test]
+  fn bad_start_positions_fail_the_list() {
+    let err = MoveList::parse("start 12345\nLL\n").err().unwrap();
+    assert!(err.starts_with("line 1: bad start position"), "{err}");
+    let err = MoveList::parse("start 123456780\nstart 123456780\n")
+      .err()
+      .unwrap();
+    assert_eq!(err, "line 2: there's a second start position");
+    let err = MoveList::parse("LL\nstart 123456780\n").err().unwrap();
+    assert_eq!(
+      err,
+      "line 2: the start position comes after some of the moves"
+    );
+  }
+
+  #[test]
+  fn unknown_moves_keep_the_moves_before() {
+    let (list, problem) = MoveList::parse("start 123456780\nrr\nuxd\nll\n").unwrap();
+    assert_eq!(
+      list.moves,
+      [Direction::Right, Direction::Right, Direction::Up]
+    );
+    assert_eq!(
+      problem.as_deref(),
+      Some("line 3: unknown move 'x' (expected u, d, l or r)")
+    );
+  }
+
+  #[test]
+  fn lists_with_nothing_to_play_fail() {
+    for text in ["", "\n\n", "# Just a comment\n"] {
+      let err = MoveList::parse(text).err();
+      assert_eq!(err.as_deref(), Some("there are no moves in it"), "{text:?}");
+    }
+    let err = MoveList::parse("hello\n").err();
+    assert_eq!(
+      err.as_deref(),
+      Some("line 1: unknown move 'h' (expected u, d, l or r)")
+    );
+  }
+}
@@ -0,0 +1,44 @@
+/// How many modules of light margin a QR code needs around it for scanners to find it.
+#[cfg(feature = "qr")]
+const QUIET_ZONE: usize = 4;
+
+/// Draw some text as a QR code, in rows of characters that each cover two modules, one
+/// above the other. Light modules are drawn as blocks and dark ones as spaces, so the
+/// rows have to be shown light on dark. Returns None if the text is too long for a QR
+/// code, or if this build doesn't have them.
+#[cfg(feature = "qr")]
+pub fn rows(text: &str) -> Option<Vec<String>> {
+  use qrcode::{Color, EcLevel, QrCode};
+
+  // Uppercase letters and digits pack in tighter than lowercase, and position codes
+  // read the same either way.
+  let code = QrCode::with_error_correction_level(text.to_uppercase(), EcLevel::L).ok()?;
+  let width = code.width();
+  let colors = code.to_colors();
+  let side = width + 2 * QUIET_ZONE;
+  let light = |x: usize, y: usize| {
+    let (Some(x), Some(y)) = (x.checked_sub(QUIET_ZONE), y.checked_sub(QUIET_ZONE)) else {
+      return true;
+    };
+    x >= width || y >= width || colors[y * width + x] == Color::Light
+  };
+  let rows = (0..side)
+    .step_by(2)
+    .map(|y| {
+      (0..side)
+        .map(|x| match (light(x, y), light(x, y + 1)) {
+          (true, true) => '█',
+          (true, false) => '▀',
+          (false, true) => '▄',
+          (false, false) => ' ',
+        })
+        .collect()
+    })
+    .collect();
+  Some(rows)
+}
+
+#[cfg(not(feature = "qr"))]
+pub fn rows(_text: &str) -> Option<Vec<String>> {
+  None
+}
@@ -0,0 +1,87 @@
+use std::path::PathBuf;
+use std::{fs, io};
+
+use serde::{Deserialize, Serialize};
+
+use crate::stats::data_dir;
+
+/// The settings file's name, in the data directory.
+pub const SETTINGS_FILE: &str = "settings.toml";
+
+/// The options the player used last time, so they don't have to give them again. Flags
+/// on the command line still win. Unlike the config file, this is written by the game.
+#[derive(Serialize, Deserialize, Default, PartialEq, Debug)]
+#[serde(default)]
+pub struct Settings {
+  pub size: Option<usize>,
+  /// The difficulty's id, like "hard" or "warmup5".
+  pub difficulty: Option<String>,
+  /// The key preset's name.
+  pub keys: Option<String>,
+  pub align: Option<String>,
+  /// The name of the built-in theme last shown.
+  pub theme: Option<String>,
+}
+
+impl Settings {
+  /// Load the settings saved last time. A missing or unreadable file just means there
+  /// aren't any, and the game's defaults are used.
+  pub fn load() -> Self {
+    path()
+      .and_then(|path| fs::read_to_string(path).ok())
+      .map(|contents| Settings::parse(&contents))
+      .unwrap_or_default()
+  }
+
+  /// Read settings from the file's contents. Anything that doesn't parse is thrown out
+  /// in favor of the defaults, since the player never wrote it by hand.
+  fn parse(contents: &str) -> Self {
+    toml::from_str(contents).unwrap_or_default()
+  }
+
+  /// Save the settings for next time.
+  pub fn save(&self) -> io::Result<()> {
+    let Some(path) = path() else {
+      return Ok(());
+    };
+    if let Some(dir) = path.parent() {
+      fs::create_dir_all(dir)?;
+    }
+    let contents = toml::to_string(self).map_err(io::Error::other)?;
+    fs::write(path, contents)
+  }
+}
+
+fn path() -> Option<PathBuf> {
+  data_dir().map(|dir| dir.join(SETTINGS_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn settings_round_trip() {
+    let settings = Settings {
+      size: Some(5),
+      difficulty: Some("warmup5".to_string()),
+      keys: Some("vim".to_string()),
+      align: Some("left".to_string()),
+      theme: Some("mono".to_string()),
+    };
+    let text = toml::to_string(&settings).unwrap();
+    assert_eq!(toml::from_str::<Settings>(&text).unwrap(), settings);
+    assert_eq!(Settings::parse(&text), settings);
+  }
+
+  #[test]
+  fn corrupt_settings_fall_back_to_the_defaults() {
+    for contents in ["", "size = ", "size = \"big\"", "\u{0}\u{1}garbage"] {
+      assert_eq!(
+        Settings::parse(contents),
+        Settings::default(),
+        "{contents:?}"
+      );
+    }
+  }
+}
@@ -0,0 +1,216 @@
+use std::fmt::Write;
+
+use ratatui::layout::{Margin, Rect};
+use ratatui::style::Color;
+
+use crate::Align;
+use crate::config::{BorderStyle, NumberAlign, Theme, TileStyle};
+use crate::grid::Grid;
+use crate::layout::LayoutConfig;
+
+/// How many pixels wide and tall each terminal cell of the layout is in a snapshot.
+/// Terminal cells are about twice as tall as they are wide.
+const CELL_WIDTH: u16 = 10;
+const CELL_HEIGHT: u16 = 20;
+
+/// The background behind the board, standing in for the terminal's own.
+const BACKGROUND: &str = "#1e1e1e";
+
+/// What the terminal shows when colors are off.
+const PLAIN: &str = "#d0d0d0";
+
+/// Draw the board as an SVG image, laid out the way the game lays it out in the
+/// terminal and in the theme's colors. With `color` off, everything is drawn in gray.
+pub fn svg(grid: &Grid, theme: &Theme, color: bool) -> String {
+  let size = grid.size();
+  let layout = LayoutConfig::for_size(size, theme.tile_style);
+  let board = layout.board_area(Rect::default(), size as u16, Align::Left);
+  // Leave a cell's width all around, so the border isn't up against the edge.
+  let board = Rect {
+    x: 1,
+    y: 1,
+    ..board
+  };
+  let paint = |c: Color| if color { hex(c) } else { PLAIN.to_string() };
+
+  let width = (board.width + 2) * CELL_WIDTH;
+  let height = (board.height + 2) * CELL_HEIGHT;
+  let mut svg = format!(
+    "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+     viewBox=\"0 0 {width} {height}\" font-family=\"monospace\" font-size=\"16\">\n"
+  );
+  let background = theme
+    .board_background
+    .filter(|_| color)
+    .map_or(BACKGROUND.to_string(), hex);
+  writeln!(
+    svg,
+    "  <rect width=\"{width}\" height=\"{height}\" fill=\"{BACKGROUND}\"/>"
+  )
+  .unwrap();
+  let border = if grid.is_solved() {
+    Color::Green
+  } else {
+    Color::Red
+  };
+  writeln!(
+    svg,
+    "  <rect {} fill=\"{background}\" stroke=\"{}\" stroke-width=\"{}\"{}/>",
+    inset(board),
+    paint(border),
+    stroke_width(theme.board_border),
+    corners(theme.board_border),
+  )
+  .unwrap();
+
+  for (y, row) in grid.rows().enumerate() {
+    for (x, &cell) in row.iter().enumerate() {
+      let tile = layout.tile(board, x, y);
+      let Some(n) = cell else {
+        if let Some(blank) = theme.blank.filter(|_| color) {
+          writeln!(svg, "  <rect {} fill=\"{}\"/>", inset(tile), hex(blank)).unwrap();
+        }
+        continue;
+      };
+      let tile_color = paint(theme.tile_color(grid, n, (x, y)));
+      let text_color = match theme.tile_style {
+        // Blocks are filled in, with the number in black on top, as in the terminal.
+        TileStyle::Grid => {
+          writeln!(svg, "  <rect {} fill=\"{tile_color}\"/>", inset(tile)).unwrap();
+          "#000000".to_string()
+        }
+        TileStyle::Bordered => {
+          writeln!(
+            svg,
+            "  <rect {} fill=\"none\" stroke=\"{tile_color}\" stroke-width=\"{}\"{}/>",
+            inset(tile),
+            stroke_width(theme.tile_border),
+            corners(theme.tile_border),
+          )
+          .unwrap();
+          tile_color
+        }
+      };
+      let (center_x, center_y) = center(tile);
+      // The number goes inside the tile's border, where the terminal would put it.
+      let inside = match theme.tile_style {
+        TileStyle::Grid => tile,
+        TileStyle::Bordered => tile.inner(Margin::new(1, 1)),
+      };
+      let (x, anchor) = match theme.number_align {
+        NumberAlign::Left => (inside.x * CELL_WIDTH, "start"),
+        NumberAlign::Center => (center_x, "middle"),
+        NumberAlign::Right => (inside.right() * CELL_WIDTH, "end"),
+      };
+      writeln!(
+        svg,
+        "  <text x=\"{x}\" y=\"{center_y}\" fill=\"{text_color}\" \
+         text-anchor=\"{anchor}\" dominant-baseline=\"central\">{}</text>",
+        theme.number(n)
+      )
+      .unwrap();
+    }
+  }
+  svg.push_str("</svg>\n");
+  svg
+}
+
+/// The position and size attributes for a rectangle covering the given cells, pulled
+/// in by half a cell's width so lines sit where the terminal would draw them.
+fn inset(area: Rect) -> String {
+  let x = area.x * CELL_WIDTH + CELL_WIDTH / 2;
+  let y = area.y * CELL_HEIGHT + CELL_HEIGHT / 2;
+  let width = (area.width * CELL_WIDTH).saturating_sub(CELL_WIDTH);
+  let height = (area.height * CELL_HEIGHT).saturating_sub(CELL_HEIGHT);
+  format!("x=\"{x}\" y=\"{y}\" width=\"{width}\" height=\"{height}\"")
+}
+
+/// The middle of the given cells, in pixels.
+fn center(area: Rect) -> (u16, u16) {
+  (
+    area.x * CELL_WIDTH + area.width * CELL_WIDTH / 2,
+    area.y * CELL_HEIGHT + area.height * CELL_HEIGHT / 2,
+  )
+}
+
+fn stroke_width(style: BorderStyle) -> u16 {
+  match style {
+    BorderStyle::Thick | BorderStyle::Double => 4,
+    BorderStyle::Rounded | BorderStyle::Plain => 2,
+  }
+}
+
+/// The attribute for rounding a rectangle's corners, if the style has round ones.
+fn corners(style: BorderStyle) -> &'static str {
+  match style {
+    BorderStyle::Rounded => " rx=\"6\"",
+    _ => "",
+  }
+}
+
+/// A color as an SVG hex code.
+fn hex(color: Color) -> String {
+  let (r, g, b) = rgb(color);
+  format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+/// The red, green and blue of a color. Named colors get common terminal values, since
+/// there's no terminal to ask what they really look like.
+fn rgb(color: Color) -> (u8, u8, u8) {
+  match color {
+    Color::Rgb(r, g, b) => (r, g, b),
+    Color::Indexed(i) => indexed(i),
+    Color::Black | Color::Reset => (0, 0, 0),
+    Color::Red => (205, 49, 49),
+    Color::Green => (13, 188, 121),
+    Color::Yellow => (229, 229, 16),
+    Color::Blue => (36, 114, 200),
+    Color::Magenta => (188, 63, 188),
+    Color::Cyan => (17, 168, 205),
+    Color::Gray => (229, 229, 229),
+    Color::DarkGray => (102, 102, 102),
+    Color::LightRed => (241, 76, 76),
+    Color::LightGreen => (35, 209, 139),
+    Color::LightYellow => (245, 245, 67),
+    Color::LightBlue => (59, 142, 234),
+    Color::LightMagenta => (214, 112, 214),
+    Color::LightCyan => (41, 184, 219),
+    Color::White => (255, 255, 255),
+  }
+}
+
+/// The xterm value of a color from the 256-color palette.
+fn indexed(i: u8) -> (u8, u8, u8) {
+  const NAMED: [Color; 16] = [
+    Color::Black,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::Gray,
+    Color::DarkGray,
+    Color::LightRed,
+    Color::LightGreen,
+    Color::LightYellow,
+    Color::LightBlue,
+    Color::LightMagenta,
+    Color::LightCyan,
+    Color::White,
+  ];
+  match i {
+    0..16 => rgb(NAMED[usize::from(i)]),
+    // A 6x6x6 cube of colors.
+    16..232 => {
+      let level = |n: u8| if n == 0 { 0 } else { 55 + n * 40 };
+      let i = i - 16;
+      (level(i / 36), level(i / 6 % 6), level(i % 6))
+    }
+    // Then 24 shades of gray.
+    232.. => {
+      let level = 8 + (i - 232) * 10;
+      (level, level, level)
+    }
+  }
+}
@@ -0,0 +1,635 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, LazyLock, Mutex};
+
+use crate::grid::{Direction, Goal, Grid};
+
+/// The largest board we solve optimally. Past this, IDA* can search for hours, so we
+/// solve layer by layer instead.
+pub const MAX_OPTIMAL_SIZE: usize = 4;
+
+/// The most tiles in one group of the pattern database. Each group's table has
+/// (cells ^ group size) entries, so five tiles on a 4x4 board is about a megabyte.
+const MAX_PATTERN_TILES: usize = 5;
+
+/// Find a sequence of moves that solves the puzzle, or None if it can't be solved.
+/// Boards up to 4x4 get the shortest possible solution. Larger boards get a longer
+/// one, since finding the shortest would take far too long.
+pub fn solve(grid: &Grid) -> Option<Vec<Direction>> {
+  if !grid.is_solvable() {
+    return None;
+  }
+  if *grid.goal() != Goal::Standard {
+    // The pattern databases are built for the standard goal. Renumbering the tiles
+    // doesn't change which moves solve the board.
+    return solve(&grid.relabeled(Goal::Standard));
+  }
+  if grid.size() <= MAX_OPTIMAL_SIZE {
+    Some(solve_optimal(grid))
+  } else {
+    Some(solve_layered(grid))
+  }
+}
+
+/// Find a solution of at most `max_moves` moves, or None if there isn't one. It isn't
+/// necessarily the shortest: the search stops at the first that fits, which can be
+/// much quicker than proving what's shortest. Boards larger than 4x4 only try the
+/// layered solution, so there None means it didn't fit, not that nothing would.
+pub fn solve_within(grid: &Grid, max_moves: usize) -> Option<Vec<Direction>> {
+  if !grid.is_solvable() {
+    return None;
+  }
+  if *grid.goal() != Goal::Standard {
+    return solve_within(&grid.relabeled(Goal::Standard), max_moves);
+  }
+  // The layered solution comes quickly, and with a generous budget it's enough.
+  let layered = solve_layered(grid);
+  if layered.len() <= max_moves {
+    return Some(layered);
+  }
+  if grid.size() > MAX_OPTIMAL_SIZE {
+    return None;
+  }
+  // A single pass of IDA* with the budget as its bound, rather than starting from the
+  // estimate and raising the bound until a solution turns up.
+  let mut search = Search::new(grid, pattern_database(grid.size()));
+  let bound = u32::try_from(max_moves).unwrap_or(u32::MAX);
+  search.search_within(0, bound, None).then_some(search.path)
+}
+
+/// The moves that walk the blank to the target cell (x, y) along a shortest path that
+/// doesn't disturb any of the tiles marked in `keep`, which is indexed row by row.
+/// Returns None if the kept tiles wall the target off.
+pub fn blank_path(grid: &Grid, target: (usize, usize), keep: &[bool]) -> Option<Vec<Direction>> {
+  let size = grid.size();
+  let (blank_x, blank_y) = grid.find_blank();
+  let start = blank_y * size + blank_x;
+  let target = target.1 * size + target.0;
+
+  let mut came_from = vec![None; size * size];
+  let mut queue = VecDeque::from([start]);
+  while let Some(blank) = queue.pop_front() {
+    if blank == target {
+      let mut directions = Vec::new();
+      let mut cell = target;
+      while cell != start {
+        let (previous, direction) = came_from[cell].unwrap();
+        directions.push(direction);
+        cell = previous;
+      }
+      directions.reverse();
+      return Some(directions);
+    }
+    for direction in Direction::ALL {
+      // The tile next to the blank slides into it, so the blank moves there.
+      let Some(next) = neighbor(size, blank, direction) else {
+        continue;
+      };
+      if next == start || came_from[next].is_some() || (keep[next] && next != target) {
+        continue;
+      }
+      came_from[next] = Some((blank, direction));
+      queue.push_back(next);
+    }
+  }
+  None
+}
+
+/// Find a shortest solution with IDA*, using a pattern database as the heuristic. The
+/// grid must be solvable.
+fn solve_optimal(grid: &Grid) -> Vec<Direction> {
+  let mut search = Search::new(grid, pattern_database(grid.size()));
+  let mut bound = search.heuristic();
+  loop {
+    match search.search(0, bound, None) {
+      None => return search.path,
+      Some(next_bound) => bound = next_bound,
+    }
+  }
+}
+
+/// Pattern databases take a moment to build, so keep them around once they're built.
+fn pattern_database(size: usize) -> Arc<PatternDatabase> {
+  static DATABASES: LazyLock<Mutex<HashMap<usize, Arc<PatternDatabase>>>> =
+    LazyLock::new(Default::default);
+  DATABASES
+    .lock()
+    .unwrap()
+    .entry(size)
+    .or_insert_with(|| Arc::new(PatternDatabase::new(size)))
+    .clone()
+}
+
+/// An additive pattern database. The tiles are split into groups, and for each group
+/// we record the fewest moves of that group's tiles needed to bring them home from
+/// every arrangement, ignoring the other tiles. Only moves of a group's own tiles are
+/// counted, so adding up the groups never overestimates the real distance.
+struct PatternDatabase {
+  size: usize,
+  /// Which group each tile is in, and its place within the group. Index 0 is the blank.
+  slots: Vec<(usize, usize)>,
+  groups: Vec<Vec<u8>>,
+  /// The fewest moves for each group, indexed by where its tiles are (see `index`).
+  costs: Vec<Vec<u8>>,
+}
+
+impl PatternDatabase {
+  fn new(size: usize) -> Self {
+    let cells = size * size;
+    let tiles = cells - 1;
+    let group_count = tiles.div_ceil(MAX_PATTERN_TILES);
+    // Split the tiles as evenly as possible, so no table is bigger than it needs to be.
+    let groups: Vec<Vec<u8>> = (0..group_count)
+      .map(|g| {
+        let start = g * tiles / group_count + 1;
+        let end = (g + 1) * tiles / group_count + 1;
+        (start as u8..end as u8).collect()
+      })
+      .collect();
+    let mut slots = vec![(0, 0); cells];
+    for (g, group) in groups.iter().enumerate() {
+      for (k, &tile) in group.iter().enumerate() {
+        slots[tile as usize] = (g, k);
+      }
+    }
+    let costs = groups
+      .iter()
+      .map(|group| group_costs(size, group))
+      .collect();
+    PatternDatabase {
+      size,
+      slots,
+      groups,
+      costs,
+    }
+  }
+}
+
+/// Where a group's tiles are, as a number: the cells of its tiles as digits in base
+/// (number of cells).
+fn index(cells: usize, positions: impl Iterator<Item = usize>) -> usize {
+  positions
+    .fold((0, 1), |(index, place), p| {
+      (index + p * place, place * cells)
+    })
+    .0
+}
+
+/// Find the fewest moves of the group's tiles to solve every arrangement of them, with
+/// a breadth-first search outward from the solved state. Moving the blank past other
+/// tiles is free, so those moves go on the front of the queue.
+fn group_costs(size: usize, group: &[u8]) -> Vec<u8> {
+  let cells = size * size;
+  let table_len = cells.pow(group.len() as u32);
+  let mut costs = vec![u8::MAX; table_len];
+  // The search state also tracks the blank, so the state is index * cells + blank.
+  let mut seen = vec![false; table_len * cells];
+
+  let goal: Vec<usize> = group
+    .iter()
+    .map(|&tile| {
+      let (x, y) = Goal::Standard.position(size, tile);
+      y * size + x
+    })
+    .collect();
+  let start = index(cells, goal.iter().copied()) * cells + (cells - 1);
+  let mut queue = VecDeque::from([(start, 0u8)]);
+  while let Some((state, cost)) = queue.pop_front() {
+    if seen[state] {
+      continue;
+    }
+    seen[state] = true;
+    let (table_index, blank) = (state / cells, state % cells);
+    costs[table_index] = costs[table_index].min(cost);
+
+    let positions = decode(cells, table_index, group.len());
+    for direction in Direction::ALL {
+      let Some(from) = neighbor(size, blank, direction) else {
+        continue;
+      };
+      // Whatever is at `from` slides into the blank.
+      match positions.iter().position(|&p| p == from) {
+        Some(k) => {
+          let moved = table_index - from * cells.pow(k as u32) + blank * cells.pow(k as u32);
+          queue.push_back((moved * cells + from, cost + 1));
+        }
+        None => queue.push_front((table_index * cells + from, cost)),
+      }
+    }
+  }
+  costs
+}
+
+/// The cells of a group's tiles, from its index.
+fn decode(cells: usize, mut index: usize, len: usize) -> Vec<usize> {
+  (0..len)
+    .map(|_| {
+      let p = index % cells;
+      index /= cells;
+      p
+    })
+    .collect()
+}
+
+/// The cell next to the given one, in the direction a tile there would have to come
+/// from to slide into it.
+fn neighbor(size: usize, cell: usize, direction: Direction) -> Option<usize> {
+  let (dx, dy) = direction.offset();
+  let x = (cell % size).checked_add_signed(dx).filter(|&x| x < size)?;
+  let y = (cell / size).checked_add_signed(dy).filter(|&y| y < size)?;
+  Some(y * size + x)
+}
+
+/// The state of an IDA* search. The heuristic is kept up to date as moves are made
+/// and unmade, rather than recomputed at every node.
+struct Search {
+  database: Arc<PatternDatabase>,
+  /// Tile numbers row by row, with 0 for the blank.
+  tiles: Vec<u8>,
+  blank: usize,
+  /// Each group's current index into its table.
+  indexes: Vec<usize>,
+  path: Vec<Direction>,
+}
+
+impl Search {
+  fn new(grid: &Grid, database: Arc<PatternDatabase>) -> Self {
+    let cells = database.size * database.size;
+    let tiles: Vec<u8> = grid
+      .rows()
+      .flatten()
+      .map(|cell| cell.unwrap_or(0))
+      .collect();
+    let blank = tiles.iter().position(|&t| t == 0).unwrap();
+    let indexes = database
+      .groups
+      .iter()
+      .map(|group| {
+        let positions = group
+          .iter()
+          .map(|&tile| tiles.iter().position(|&t| t == tile).unwrap());
+        index(cells, positions)
+      })
+      .collect();
+    Search {
+      database,
+      tiles,
+      blank,
+      indexes,
+      path: Vec::new(),
+    }
+  }
+
+  fn heuristic(&self) -> u32 {
+    self
+      .indexes
+      .iter()
+      .zip(&self.database.costs)
+      .map(|(&index, costs)| costs[index] as u32)
+      .sum()
+  }
+
+  /// Make a move, keeping the group indexes up to date. Returns false if the move
+  /// isn't possible.
+  fn apply(&mut self, direction: Direction) -> bool {
+    let size = self.database.size;
+    let Some(from) = neighbor(size, self.blank, direction) else {
+      return false;
+    };
+    let tile = self.tiles[from];
+    let (g, k) = self.database.slots[tile as usize];
+    let place = (size * size).pow(k as u32);
+    self.indexes[g] = self.indexes[g] - from * place + self.blank * place;
+    self.tiles.swap(from, self.blank);
+    self.blank = from;
+    true
+  }
+
+  /// Search for a solution within the bound. Returns None if one was found (and left in
+  /// self.path), or else the smallest estimate that went over the bound.
+  fn search(&mut self, moves: u32, bound: u32, last: Option<Direction>) -> Option<u32> {
+    let heuristic = self.heuristic();
+    let estimate = moves + heuristic;
+    if estimate > bound {
+      return Some(estimate);
+    }
+    if heuristic == 0 {
+      return None;
+    }
+    let mut min = u32::MAX;
+    for direction in Direction::ALL {
+      if Some(direction.opposite()) == last || !self.apply(direction) {
+        continue;
+      }
+      self.path.push(direction);
+      match self.search(moves + 1, bound, Some(direction)) {
+        None => return None,
+        Some(over) => min = min.min(over),
+      }
+      self.path.pop();
+      self.apply(direction.opposite());
+    }
+    Some(min)
+  }
+
+  /// Search for any solution within the bound, leaving it in self.path. Unlike
+  /// `search`, the moves that look closest to solved are tried first, so with room to
+  /// spare it heads more or less straight for a solution.
+  fn search_within(&mut self, moves: u32, bound: u32, last: Option<Direction>) -> bool {
+    let heuristic = self.heuristic();
+    if moves + heuristic > bound {
+      return false;
+    }
+    if heuristic == 0 {
+      return true;
+    }
+    let mut next = Vec::new();
+    for direction in Direction::ALL {
+      if Some(direction.opposite()) == last || !self.apply(direction) {
+        continue;
+      }
+      next.push((self.heuristic(), direction));
+      self.apply(direction.opposite());
+    }
+    next.sort_by_key(|&(estimate, _)| estimate);
+    for (_, direction) in next {
+      self.apply(direction);
+      self.path.push(direction);
+      if self.search_within(moves + 1, bound, Some(direction)) {
+        return true;
+      }
+      self.path.pop();
+      self.apply(direction.opposite());
+    }
+    false
+  }
+}
+
+/// Solve the way people do: finish the top row, then the left column, and repeat on
+/// the smaller puzzle that's left, until only a 2x2 corner remains. The grid must be
+/// solvable.
+fn solve_layered(grid: &Grid) -> Vec<Direction> {
+  let size = grid.size();
+  let mut solver = Layered {
+    grid: grid.clone(),
+    locked: vec![false; size * size],
+    moves: Vec::new(),
+  };
+  let (mut top, mut left) = (0, 0);
+  while size - top > 2 || size - left > 2 {
+    if size - top >= size - left {
+      let cells: Vec<_> = (left..size).map(|x| (x, top)).collect();
+      solver.solve_line(&cells);
+      top += 1;
+    } else {
+      let cells: Vec<_> = (top..size).map(|y| (left, y)).collect();
+      solver.solve_line(&cells);
+      left += 1;
+    }
+  }
+  let corner = [(left, top), (left + 1, top), (left, top + 1)];
+  solver.place(&corner);
+  solver.moves
+}
+
+/// The moves that fill in row y when the rows above it are finished and all but one of
+/// its tiles are in place. The rows above, the row's tiles before the missing one, and
+/// the cells marked in `keep`, which is indexed row by row, all stay put. Returns None
+/// if the row isn't one tile short after finished rows, it doesn't have two rows below
+/// it to make room, or the kept cells are in the way.
+pub fn finish_row(grid: &Grid, y: usize, keep: &[bool]) -> Option<Vec<Direction>> {
+  let size = grid.size();
+  if y + 2 >= size || !(0..y).all(|row| grid.row_complete(row)) {
+    return None;
+  }
+  let placed = grid.placed();
+  let missing: Vec<usize> = (0..size).filter(|&x| !placed[y * size + x]).collect();
+  let [missing] = missing[..] else {
+    return None;
+  };
+  let mut solver = Layered {
+    grid: grid.clone(),
+    locked: keep.to_vec(),
+    moves: Vec::new(),
+  };
+  // Fill in the row from the missing tile on, the way the layered solver would, which
+  // takes at least two tiles.
+  let start = missing.min(size - 2);
+  solver.locked[..y * size + start].fill(true);
+  let cells: Vec<_> = (start..size).map(|x| (x, y)).collect();
+  let (first, last_two) = cells.split_at(cells.len() - 2);
+  for &cell in first {
+    solver.try_place(&[cell])?;
+  }
+  solver.try_place(last_two)?;
+  Some(solver.moves)
+}
+
+/// The state of the layer-by-layer solver.
+struct Layered {
+  grid: Grid,
+  /// Cells whose tiles are in place and must not be disturbed.
+  locked: Vec<bool>,
+  moves: Vec<Direction>,
+}
+
+impl Layered {
+  fn index(&self, (x, y): (usize, usize)) -> usize {
+    y * self.grid.size() + x
+  }
+
+  /// Fill a row or column with its tiles, locking each cell once it's done. There have
+  /// to be at least two more unsolved lines beyond it to make room.
+  fn solve_line(&mut self, cells: &[(usize, usize)]) {
+    let (first, last_two) = cells.split_at(cells.len() - 2);
+    for &cell in first {
+      self.place(&[cell]);
+    }
+    // Placing the last tile on its own would disturb the one before it, so place them
+    // together.
+    self.place(last_two);
+  }
+
+  /// Bring the tiles that belong in the given cells to them, then lock the cells.
+  fn place(&mut self, cells: &[(usize, usize)]) {
+    self
+      .try_place(cells)
+      .expect("The tiles should always have room to reach their cells.");
+  }
+
+  /// Like `place`, but returns None if locked cells keep the tiles from getting there.
+  /// This is a breadth-first search over where those tiles and the blank are, ignoring
+  /// the other unlocked tiles, which get pushed around freely.
+  fn try_place(&mut self, cells: &[(usize, usize)]) -> Option<()> {
+    let size = self.grid.size();
+    let targets: Vec<usize> = cells.iter().map(|&cell| self.index(cell)).collect();
+    let start: Vec<usize> = cells
+      .iter()
+      .map(|&(x, y)| self.grid.goal().target(size, x, y))
+      .chain([None])
+      .map(|cell| self.index(self.grid.find(cell)))
+      .collect();
+
+    let mut came_from = HashMap::from([(start.clone(), None)]);
+    let mut queue = VecDeque::from([start]);
+    let mut end = None;
+    while let Some(state) = queue.pop_front() {
+      if state[..targets.len()] == targets[..] {
+        end = Some(state);
+        break;
+      }
+      let blank = state[targets.len()];
+      for direction in Direction::ALL {
+        let Some(tile) = neighbor(size, blank, direction).filter(|&tile| !self.locked[tile]) else {
+          continue;
+        };
+        // The tile slides into the blank, and the blank takes its place.
+        let next: Vec<usize> = state
+          .iter()
+          .map(|&i| {
+            if i == tile {
+              blank
+            } else if i == blank {
+              tile
+            } else {
+              i
+            }
+          })
+          .collect();
+        if !came_from.contains_key(&next) {
+          came_from.insert(next.clone(), Some((state.clone(), direction)));
+          queue.push_back(next);
+        }
+      }
+    }
+
+    let mut state = end?;
+    let mut directions = Vec::new();
+    while let Some((previous, direction)) = came_from[&state].clone() {
+      directions.push(direction);
+      state = previous;
+    }
+    for direction in directions.into_iter().rev() {
+      self.grid.slide(direction);
+      self.moves.push(direction);
+    }
+    for target in targets {
+      self.locked[target] = true;
+    }
+    Some(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use rand::SeedableRng;
+  use rand::rngs::StdRng;
+
+  use super::*;
+  use crate::grid::{new_grid_with, scramble_grid};
+
+  /// The grid after making the moves, checking that each one can be made.
+  fn play(grid: &Grid, moves: &[Direction]) -> Grid {
+    let mut grid = grid.clone();
+    for &direction in moves {
+      assert!(
+        grid.slide(direction),
+        "{direction:?} can't be made on\n{grid}"
+      );
+    }
+    grid
+  }
+
+  #[test]
+  fn layered_solution_solves_a_scrambled_5x5() {
+    for seed in 0..20 {
+      let grid = new_grid_with(5, Goal::Standard, &mut StdRng::seed_from_u64(seed));
+      assert!(
+        play(&grid, &solve_layered(&grid)).is_solved(),
+        "seed {seed} wasn't solved:\n{grid}"
+      );
+    }
+  }
+
+  #[test]
+  fn solutions_reach_the_reverse_goal() {
+    for size in [3, 4, 5] {
+      for seed in 0..5 {
+        let grid = new_grid_with(size, Goal::Reverse, &mut StdRng::seed_from_u64(seed));
+        let moves = solve(&grid).unwrap();
+        assert!(
+          play(&grid, &moves).is_solved(),
+          "{size}x{size} seed {seed} wasn't solved:\n{grid}"
+        );
+      }
+    }
+  }
+
+  #[test]
+  fn solutions_reach_a_custom_goal() {
+    // The tiles in columns instead of rows, and snaking back and forth.
+    for code in ["159d26ae37bf48c0", "123487659abcfed0"] {
+      let goal = Goal::custom(code).unwrap();
+      for seed in 0..3 {
+        let grid = new_grid_with(4, goal.clone(), &mut StdRng::seed_from_u64(seed));
+        let solved = play(&grid, &solve(&grid).unwrap());
+        assert!(
+          solved.is_solved(),
+          "{code} seed {seed} wasn't solved:\n{grid}"
+        );
+        assert_eq!(solved.code(), code);
+      }
+    }
+  }
+
+  /// Check that a budget short of the fewest moves finds nothing, and any budget from
+  /// there up finds a solution that fits.
+  fn check_budgets(grid: &Grid) {
+    let optimal = solve(grid).unwrap().len();
+    assert_eq!(
+      solve_within(grid, optimal.saturating_sub(1)),
+      None,
+      "{optimal} moves fit in fewer:\n{grid}"
+    );
+    for budget in [optimal, optimal + 1, optimal + 10, optimal + 100] {
+      let moves = solve_within(grid, budget).unwrap();
+      assert!(
+        moves.len() <= budget,
+        "{} moves for {budget}:\n{grid}",
+        moves.len()
+      );
+      assert!(
+        play(grid, &moves).is_solved(),
+        "not solved in {budget}:\n{grid}"
+      );
+    }
+  }
+
+  #[test]
+  fn budgets_around_the_fewest_moves() {
+    for seed in 0..10 {
+      check_budgets(&new_grid_with(
+        3,
+        Goal::Standard,
+        &mut StdRng::seed_from_u64(seed),
+      ));
+    }
+    for seed in 0..5 {
+      let grid = scramble_grid(4, Goal::Standard, &mut StdRng::seed_from_u64(seed), 40);
+      check_budgets(&grid);
+    }
+  }
+
+  #[test]
+  fn a_solved_board_fits_any_budget() {
+    assert_eq!(
+      solve_within(&Grid::solved(4, Goal::Standard), 0),
+      Some(Vec::new())
+    );
+  }
+
+  #[test]
+  fn an_unsolvable_board_fits_no_budget() {
+    let grid = Grid::from_code("123456789abcdfe0").unwrap();
+    assert_eq!(solve_within(&grid, 1000), None);
+  }
+}
@@ -0,0 +1,529 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+use std::{fs, io};
+
+use crate::grid::{Direction, Goal};
+
+/// The files in the data directory that hold records, so they can all be cleared at
+/// once. Anything else there, like saved move lists, is left alone.
+pub const STATS_FILES: [&str; 4] = [
+  BEST_MOVES_FILE,
+  BEST_RUNS_FILE,
+  BEST_TOURNAMENTS_FILE,
+  RECENT_SOLVES_FILE,
+];
+
+const BEST_MOVES_FILE: &str = "best_moves.txt";
+const BEST_RUNS_FILE: &str = "best_runs.txt";
+const BEST_TOURNAMENTS_FILE: &str = "best_tournaments.txt";
+const RECENT_SOLVES_FILE: &str = "recent_solves.txt";
+
+/// The directory for files that should survive between runs, following the XDG convention.
+pub fn data_dir() -> Option<PathBuf> {
+  if let Some(dir) = std::env::var_os("XDG_DATA_HOME").filter(|dir| !dir.is_empty()) {
+    return Some(PathBuf::from(dir).join("slyde"));
+  }
+  std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share/slyde"))
+}
+
+/// What counts as one move when scoring a solve. Sliding a whole row with one key
+/// press moves several tiles, and players disagree about whether that's one move or
+/// several.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum MoveMetric {
+  /// Every tile that slides is a move.
+  #[default]
+  Slides,
+  /// Every key press that moves tiles is a move.
+  KeyPresses,
+}
+
+impl MoveMetric {
+  pub fn name(self) -> &'static str {
+    match self {
+      MoveMetric::Slides => "slides",
+      MoveMetric::KeyPresses => "keys",
+    }
+  }
+}
+
+impl FromStr for MoveMetric {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    [MoveMetric::Slides, MoveMetric::KeyPresses]
+      .into_iter()
+      .find(|metric| metric.name() == s)
+      .ok_or_else(|| format!("unknown move metric {s:?} (expected slides or keys)"))
+  }
+}
+
+/// The name a record is kept under: the starting position's code, with the goal and
+/// how its moves were counted added unless they're the defaults, so only like is
+/// compared with like.
+pub fn record_key(code: &str, goal: &Goal, metric: MoveMetric) -> String {
+  let mut key = code.to_string();
+  if *goal != Goal::Standard {
+    key = format!("{key}-{}", goal.id());
+  }
+  if metric != MoveMetric::Slides {
+    key = format!("{key}-{}", metric.name());
+  }
+  key
+}
+
+/// The fewest moves the player has needed to solve each starting position, keyed by
+/// record_key. Stored as one "key moves" pair per line. The default has none, and is
+/// never saved.
+#[derive(Default)]
+pub struct BestMoves {
+  path: Option<PathBuf>,
+  records: HashMap<String, u32>,
+}
+
+impl BestMoves {
+  /// Load the records saved by previous runs. A missing or unreadable file just means
+  /// there are no records yet.
+  pub fn load() -> Self {
+    let path = data_dir().map(|dir| dir.join(BEST_MOVES_FILE));
+    let records = path
+      .as_ref()
+      .and_then(|path| fs::read_to_string(path).ok())
+      .map(|contents| parse_records(&contents))
+      .unwrap_or_default();
+    BestMoves { path, records }
+  }
+
+  /// Load someone else's records to compare against, from a file in the same format.
+  /// These are never saved back. Lines that don't make sense are skipped, with a
+  /// warning, since the file may come from a different version of the game.
+  pub fn reference(path: &Path) -> io::Result<(Self, Vec<String>)> {
+    let contents = fs::read_to_string(path)?;
+    let records = parse_records(&contents);
+    let mut warnings = Vec::new();
+    let lines = contents
+      .lines()
+      .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+      .count();
+    if records.is_empty() && lines > 0 {
+      warnings.push(format!(
+        "{} doesn't look like a scores file (expected \"code moves\" lines)",
+        path.display()
+      ));
+    } else if records.len() < lines {
+      warnings.push(format!(
+        "{}: skipped lines that aren't \"code moves\" pairs ({} of {lines})",
+        path.display(),
+        lines - records.len()
+      ));
+    }
+    Ok((
+      BestMoves {
+        path: None,
+        records,
+      },
+      warnings,
+    ))
+  }
+
+  /// The fewest moves used to solve the position under the given key, if it's been
+  /// solved before.
+  pub fn get(&self, key: &str) -> Option<u32> {
+    self.records.get(key).copied()
+  }
+
+  /// Record a solve. Returns true if it beat the previous best, or if this is the first
+  /// time the position has been solved.
+  pub fn record(&mut self, key: &str, moves: u32) -> io::Result<bool> {
+    if self.get(key).is_some_and(|best| best <= moves) {
+      return Ok(false);
+    }
+    self.records.insert(key.to_string(), moves);
+    self.save()?;
+    Ok(true)
+  }
+
+  fn save(&self) -> io::Result<()> {
+    let Some(path) = &self.path else {
+      return Ok(());
+    };
+    if let Some(dir) = path.parent() {
+      fs::create_dir_all(dir)?;
+    }
+    let mut lines: Vec<_> = self
+      .records
+      .iter()
+      .map(|(code, moves)| format!("{code} {moves}\n"))
+      .collect();
+    lines.sort();
+    fs::write(path, lines.concat())
+  }
+}
+
+/// Every tile slid on the way to a solve, with how far into the solve it slid.
+pub type Run = Vec<(Duration, Direction)>;
+
+/// The moves of the player's best solve of each starting position, keyed by
+/// record_key, so they can race it. Stored one position per line: the key, then each
+/// move as the milliseconds into the solve followed by its letter, like "1250U". The
+/// default has none, and is never saved.
+#[derive(Default)]
+pub struct BestRuns {
+  path: Option<PathBuf>,
+  runs: HashMap<String, Run>,
+}
+
+impl BestRuns {
+  /// Load the runs saved by previous games. A missing or unreadable file just means
+  /// there aren't any yet, and lines that don't make sense are skipped.
+  pub fn load() -> Self {
+    let path = data_dir().map(|dir| dir.join(BEST_RUNS_FILE));
+    let runs = path
+      .as_ref()
+      .and_then(|path| fs::read_to_string(path).ok())
+      .map(|contents| contents.lines().filter_map(parse_run).collect())
+      .unwrap_or_default();
+    BestRuns { path, runs }
+  }
+
+  /// The best run from the position under the given key, if it's been solved before.
+  pub fn get(&self, key: &str) -> Option<&Run> {
+    self.runs.get(key)
+  }
+
+  /// Keep a run as the best from its position, replacing any from before.
+  pub fn record(&mut self, key: &str, run: Run) -> io::Result<()> {
+    self.runs.insert(key.to_string(), run);
+    let Some(path) = &self.path else {
+      return Ok(());
+    };
+    if let Some(dir) = path.parent() {
+      fs::create_dir_all(dir)?;
+    }
+    let mut lines: Vec<_> = self
+      .runs
+      .iter()
+      .map(|(code, run)| {
+        let moves: Vec<_> = run
+          .iter()
+          .map(|(time, direction)| format!("{}{}", time.as_millis(), direction.letter()))
+          .collect();
+        format!("{code} {}\n", moves.join(" "))
+      })
+      .collect();
+    lines.sort();
+    fs::write(path, lines.concat())
+  }
+}
+
+/// Parse one line of the runs file.
+fn parse_run(line: &str) -> Option<(String, Run)> {
+  let mut fields = line.split_whitespace();
+  let code = fields.next()?.to_string();
+  let run = fields
+    .map(|field| {
+      let letter = field.chars().last()?;
+      let millis = field[..field.len() - letter.len_utf8()].parse().ok()?;
+      Some((
+        Duration::from_millis(millis),
+        Direction::from_letter(letter)?,
+      ))
+    })
+    .collect::<Option<_>>()?;
+  Some((code, run))
+}
+
+/// The combined result of a tournament: every puzzle's moves, and the time taken from
+/// the first puzzle to the last.
+#[derive(Clone, Copy, Debug)]
+pub struct TournamentResult {
+  pub moves: u32,
+  pub time: Duration,
+}
+
+impl TournamentResult {
+  /// The score to beat: one point per move and one per second, lower being better.
+  pub fn score(&self) -> u64 {
+    u64::from(self.moves) + self.time.as_secs()
+  }
+}
+
+/// The best result for each tournament, keyed by a name for its set of puzzles. Stored
+/// as one "name moves milliseconds" line per tournament.
+pub struct BestTournaments {
+  path: Option<PathBuf>,
+  records: HashMap<String, TournamentResult>,
+}
+
+impl BestTournaments {
+  /// Load the results saved by previous runs. A missing or unreadable file just means
+  /// there are no results yet.
+  pub fn load() -> Self {
+    let path = data_dir().map(|dir| dir.join(BEST_TOURNAMENTS_FILE));
+    let records = path
+      .as_ref()
+      .and_then(|path| fs::read_to_string(path).ok())
+      .map(|contents| {
+        contents
+          .lines()
+          .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let name = fields.next()?.to_string();
+            let moves = fields.next()?.parse().ok()?;
+            let time = Duration::from_millis(fields.next()?.parse().ok()?);
+            Some((name, TournamentResult { moves, time }))
+          })
+          .collect()
+      })
+      .unwrap_or_default();
+    BestTournaments { path, records }
+  }
+
+  /// The best result for the named tournament, if it's been finished before.
+  pub fn get(&self, name: &str) -> Option<TournamentResult> {
+    self.records.get(name).copied()
+  }
+
+  /// Record a finished tournament. Returns true if it beat the previous best, or if
+  /// this is the first time the tournament has been finished.
+  pub fn record(&mut self, name: &str, result: TournamentResult) -> io::Result<bool> {
+    if self
+      .get(name)
+      .is_some_and(|best| best.score() <= result.score())
+    {
+      return Ok(false);
+    }
+    self.records.insert(name.to_string(), result);
+    self.save()?;
+    Ok(true)
+  }
+
+  fn save(&self) -> io::Result<()> {
+    let Some(path) = &self.path else {
+      return Ok(());
+    };
+    if let Some(dir) = path.parent() {
+      fs::create_dir_all(dir)?;
+    }
+    let mut lines: Vec<_> = self
+      .records
+      .iter()
+      .map(|(name, result)| format!("{name} {} {}\n", result.moves, result.time.as_millis()))
+      .collect();
+    lines.sort();
+    fs::write(path, lines.concat())
+  }
+}
+
+/// How many recent solve times to keep for each board size: enough to compare the
+/// latest `TREND_SOLVES` with the ones before.
+const RECENT_SOLVES: usize = 2 * TREND_SOLVES;
+
+/// How many solves go into each side of a trend.
+pub const TREND_SOLVES: usize = 5;
+
+/// The average times of the latest few solves and of the few before them.
+pub struct Trend {
+  pub recent: Duration,
+  pub previous: Duration,
+}
+
+/// One of the recent solves.
+struct RecentSolve {
+  time: Duration,
+  /// How many moves it took, and at what difficulty. Files from older versions only
+  /// have the time.
+  moves: Option<(u32, String)>,
+  /// Whether it was a clean solve, with no undos, checkpoints or help.
+  clean: bool,
+}
+
+/// How the latest solves on each size of board went, oldest first. Stored as one
+/// "size milliseconds moves difficulty" line per solve, with "clean" on the end for a
+/// clean solve. The default has none, and is never saved.
+#[derive(Default)]
+pub struct RecentSolves {
+  path: Option<PathBuf>,
+  solves: HashMap<usize, Vec<RecentSolve>>,
+}
+
+impl RecentSolves {
+  /// Load the times saved by previous runs. A missing or unreadable file just means
+  /// there aren't any yet.
+  pub fn load() -> Self {
+    let path = data_dir().map(|dir| dir.join(RECENT_SOLVES_FILE));
+    let mut solves: HashMap<usize, Vec<RecentSolve>> = HashMap::new();
+    let lines = path
+      .as_ref()
+      .and_then(|path| fs::read_to_string(path).ok())
+      .unwrap_or_default();
+    for line in lines.lines() {
+      let mut fields = line.split_whitespace();
+      let (Some(Ok(size)), Some(Ok(millis))) =
+        (fields.next().map(str::parse), fields.next().map(str::parse))
+      else {
+        continue;
+      };
+      let moves = fields.next().and_then(|moves| moves.parse().ok());
+      let difficulty = fields.next().map(str::to_string);
+      let clean = fields.next() == Some("clean");
+      solves.entry(size).or_default().push(RecentSolve {
+        time: Duration::from_millis(millis),
+        moves: moves.zip(difficulty),
+        clean,
+      });
+    }
+    RecentSolves { path, solves }
+  }
+
+  /// How many solves on this size of board there are to go on.
+  pub fn count(&self, size: usize) -> usize {
+    self.solves.get(&size).map_or(0, Vec::len)
+  }
+
+  /// Add a solve, forgetting the oldest once there are more than we keep.
+  pub fn record(
+    &mut self,
+    size: usize,
+    time: Duration,
+    moves: u32,
+    difficulty: &str,
+    clean: bool,
+  ) -> io::Result<()> {
+    let solves = self.solves.entry(size).or_default();
+    solves.push(RecentSolve {
+      time,
+      moves: Some((moves, difficulty.to_string())),
+      clean,
+    });
+    let extra = solves.len().saturating_sub(RECENT_SOLVES);
+    solves.drain(..extra);
+    self.save()
+  }
+
+  /// The moves taken by the recent solves on this size of board at the given
+  /// difficulty, oldest first.
+  pub fn moves(&self, size: usize, difficulty: &str) -> Vec<u32> {
+    self
+      .solves
+      .get(&size)
+      .into_iter()
+      .flatten()
+      .filter_map(|solve| match &solve.moves {
+        Some((moves, name)) if name == difficulty => Some(*moves),
+        _ => None,
+      })
+      .collect()
+  }
+
+  /// How many of the recent solves on this size of board were clean.
+  pub fn clean(&self, size: usize) -> usize {
+    self.solves.get(&size).map_or(0, |solves| {
+      solves.iter().filter(|solve| solve.clean).count()
+    })
+  }
+
+  /// The latest solves on this size of board compared with the ones before, or None if
+  /// there haven't been enough yet.
+  pub fn trend(&self, size: usize) -> Option<Trend> {
+    let times: Vec<_> = self
+      .solves
+      .get(&size)?
+      .iter()
+      .map(|solve| solve.time)
+      .collect();
+    if times.len() < RECENT_SOLVES {
+      return None;
+    }
+    let (previous, recent) = times.split_at(times.len() - TREND_SOLVES);
+    let average = |times: &[Duration]| times.iter().sum::<Duration>() / times.len() as u32;
+    Some(Trend {
+      recent: average(recent),
+      previous: average(&previous[previous.len() - TREND_SOLVES..]),
+    })
+  }
+
+  fn save(&self) -> io::Result<()> {
+    let Some(path) = &self.path else {
+      return Ok(());
+    };
+    if let Some(dir) = path.parent() {
+      fs::create_dir_all(dir)?;
+    }
+    let mut sizes: Vec<_> = self.solves.keys().collect();
+    sizes.sort();
+    let lines: String = sizes
+      .into_iter()
+      .flat_map(|size| {
+        self.solves[size]
+          .iter()
+          .map(move |solve| match &solve.moves {
+            Some((moves, difficulty)) => {
+              let clean = if solve.clean { " clean" } else { "" };
+              format!(
+                "{size} {} {moves} {difficulty}{clean}\n",
+                solve.time.as_millis()
+              )
+            }
+            None => format!("{size} {}\n", solve.time.as_millis()),
+          })
+      })
+      .collect();
+    fs::write(path, lines)
+  }
+}
+
+/// Parse the saved records, skipping any lines that don't make sense.
+fn parse_records(contents: &str) -> HashMap<String, u32> {
+  contents
+    .lines()
+    .filter_map(|line| {
+      let (code, moves) = line.split_once(' ')?;
+      Some((code.to_string(), moves.trim().parse().ok()?))
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn records_counted_differently_are_kept_apart() {
+    let mut best = BestMoves::default();
+    let code = "123456789abc0def";
+    // Slides are what records were always counted in, so older files still work.
+    let slides = record_key(code, &Goal::Standard, MoveMetric::Slides);
+    assert_eq!(slides, code);
+    let keys = record_key(code, &Goal::Standard, MoveMetric::KeyPresses);
+    assert!(best.record(&slides, 10).unwrap());
+    assert_eq!(best.get(&keys), None);
+    // Four key presses don't beat ten slides, or stop them being beaten.
+    assert!(best.record(&keys, 4).unwrap());
+    assert!(!best.record(&keys, 5).unwrap());
+    assert!(best.record(&slides, 8).unwrap());
+    assert_eq!(best.get(&slides), Some(8));
+    assert_eq!(best.get(&keys), Some(4));
+  }
+
+  #[test]
+  fn goals_from_the_same_start_are_kept_apart() {
+    let mut best = BestMoves::default();
+    let mut runs = BestRuns::default();
+    let code = "123456789abc0def";
+    let standard = record_key(code, &Goal::Standard, MoveMetric::Slides);
+    let reverse = record_key(code, &Goal::Reverse, MoveMetric::Slides);
+    assert_ne!(standard, reverse);
+    assert!(best.record(&standard, 3).unwrap());
+    runs
+      .record(&standard, vec![(Duration::ZERO, Direction::Up)])
+      .unwrap();
+    assert_eq!(best.get(&reverse), None);
+    assert!(runs.get(&reverse).is_none());
+    // Solving into the other goal takes more moves, but it's a record of its own.
+    assert!(best.record(&reverse, 40).unwrap());
+    assert_eq!(best.get(&standard), Some(3));
+  }
+}
@@ -0,0 +1,66 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::Stylize;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::Widget;
+
+/// Space between the columns of the status bar.
+const SPACING: u16 = 2;
+
+/// One labelled value in the status bar.
+pub struct Field {
+  label: &'static str,
+  value: String,
+  /// How wide the column is, so it doesn't shift around as the value changes.
+  width: u16,
+  /// Whether to gray out the value, because there's nothing there to use right now.
+  dim: bool,
+}
+
+impl Field {
+  pub fn new(label: &'static str, value: String, width: u16) -> Self {
+    let width = width.max((label.len() + 2 + value.len()) as u16);
+    Field {
+      label,
+      value,
+      width,
+      dim: false,
+    }
+  }
+
+  /// Gray out the value if `dim` is set.
+  pub fn dimmed(self, dim: bool) -> Self {
+    Field { dim, ..self }
+  }
+}
+
+/// A row of labelled values in fixed-width columns. The fields go in order of
+/// importance: if they don't all fit, the ones at the end are dropped first.
+pub struct StatusBar {
+  pub fields: Vec<Field>,
+}
+
+impl Widget for StatusBar {
+  fn render(self, area: Rect, buf: &mut Buffer) {
+    let mut fields = self.fields;
+    let width = |fields: &[Field]| {
+      let gaps = fields.len().saturating_sub(1) as u16 * SPACING;
+      fields.iter().map(|field| field.width).sum::<u16>() + gaps
+    };
+    while width(&fields) > area.width {
+      fields.pop();
+    }
+
+    let columns = Layout::horizontal(fields.iter().map(|field| Constraint::Length(field.width)))
+      .spacing(SPACING)
+      .split(area);
+    for (field, column) in fields.into_iter().zip(columns.iter()) {
+      let value = if field.dim {
+        Span::from(field.value).dim()
+      } else {
+        Span::from(field.value)
+      };
+      Line::from(vec![Span::from(format!("{}: ", field.label)).dim(), value]).render(*column, buf);
+    }
+  }
+}
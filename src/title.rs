@@ -0,0 +1,33 @@
+use std::io::{self, Write};
+
+use crossterm::execute;
+use crossterm::terminal::SetTitle;
+
+/// Ask the terminal to remember its current title, so it can be put back on exit.
+/// There's no portable way to read the title, but most terminals keep a stack of them.
+pub fn save() -> io::Result<()> {
+  let mut stdout = io::stdout();
+  stdout.write_all(b"\x1b[22;0t")?;
+  stdout.flush()
+}
+
+/// Put back the title saved by `save`.
+pub fn restore() -> io::Result<()> {
+  let mut stdout = io::stdout();
+  stdout.write_all(b"\x1b[23;0t")?;
+  stdout.flush()
+}
+
+pub fn set(title: &str) -> io::Result<()> {
+  execute!(io::stdout(), SetTitle(title))
+}
+
+/// Put the title back even if the game panics, after whatever the existing panic hook
+/// does.
+pub fn restore_on_panic() {
+  let hook = std::panic::take_hook();
+  std::panic::set_hook(Box::new(move |info| {
+    let _ = restore();
+    hook(info);
+  }));
+}
@@ -0,0 +1,295 @@
+//! Runs slyde the way scripts do, with the flags that print and exit instead of
+//! starting the game.
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+/// A command that runs slyde with its own empty data and config directories, named
+/// for the test, so the player's settings and records don't change what it does.
+fn slyde(test: &str, args: &[&str]) -> Command {
+  let home = home(test);
+  let mut command = Command::new(env!("CARGO_BIN_EXE_slyde"));
+  command
+    .args(args)
+    .env("HOME", &home)
+    .env("XDG_DATA_HOME", home.join("data"))
+    .env("XDG_CONFIG_HOME", home.join("config"));
+  for (name, _) in std::env::vars().filter(|(name, _)| name.starts_with("SLYDE_")) {
+    command.env_remove(name);
+  }
+  command
+}
+
+/// An empty directory for the test to use as its home.
+fn home(test: &str) -> PathBuf {
+  let home = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(test);
+  let _ = std::fs::remove_dir_all(&home);
+  std::fs::create_dir_all(&home).unwrap();
+  home
+}
+
+fn run(test: &str, args: &[&str]) -> Output {
+  slyde(test, args).output().unwrap()
+}
+
+/// Run slyde with the given text on standard input.
+fn run_with_input(test: &str, args: &[&str], input: &str) -> Output {
+  let mut child = slyde(test, args)
+    .stdin(Stdio::piped())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()
+    .unwrap();
+  child
+    .stdin
+    .take()
+    .unwrap()
+    .write_all(input.as_bytes())
+    .unwrap();
+  child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+  String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr(output: &Output) -> String {
+  String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn moves_are_played_on_the_start_position() {
+  let output = run(
+    "moves_are_played",
+    &["--demo-static", "--moves", "rd", "--exit-after"],
+  );
+  assert_eq!(
+    stdout(&output),
+    " 1  2  3  4\n 5  6  7  8\n11  . 13 15\n10 12  9 14\n"
+  );
+  assert_eq!(output.status.code(), Some(1), "{}", stderr(&output));
+}
+
+#[test]
+fn moves_that_solve_the_board_succeed() {
+  let output = run(
+    "moves_solve",
+    &[
+      "--demo-static",
+      "--moves",
+      "DRRULLLDRRRULLL",
+      "--exit-after",
+    ],
+  );
+  assert_eq!(
+    stdout(&output),
+    " 1  2  3  4\n 5  6  7  8\n 9 10 11 12\n13 14 15  .\n"
+  );
+  assert!(output.status.success(), "{}", stderr(&output));
+}
+
+#[test]
+fn unknown_moves_are_rejected() {
+  let output = run("unknown_moves", &["--demo-static", "--moves", "rx"]);
+  assert_eq!(output.status.code(), Some(2));
+  assert!(stderr(&output).contains("Unknown move 'x' in --moves"));
+}
+
+#[test]
+fn moves_that_cant_be_made_are_rejected() {
+  let output = run(
+    "impossible_moves",
+    &["--demo-static", "--moves", "rdrdrdrd"],
+  );
+  assert_eq!(output.status.code(), Some(2));
+  assert!(stderr(&output).contains("move 5 in --moves (right) can't be made"));
+}
+
+/// The solver on bench's ten seeded 4x4 puzzles, which it solves in under a second
+/// each with the optimizations tests build with. Taking three means it's got a lot
+/// slower.
+#[test]
+fn solver_keeps_up_on_the_bench_puzzles() {
+  let output = run("bench", &["bench", "--count", "10", "--limit", "3"]);
+  assert!(
+    output.status.success(),
+    "{}{}",
+    stdout(&output),
+    stderr(&output)
+  );
+}
+
+#[test]
+fn custom_goals_for_another_size_are_rejected() {
+  // Kept apart from the home the commands get, which starts out empty.
+  let goal = home("goal_size_files").join("goal");
+  std::fs::write(&goal, "87654321fedcba90\n").unwrap();
+  let goal = goal.to_str().unwrap();
+  for args in [
+    &["--goal-file", goal, "--size", "5", "--exit-after"][..],
+    &["--goal-file", goal, "--size", "3", "--exit-after"],
+  ] {
+    let output = slyde("goal_size", args).output().unwrap();
+    assert_eq!(output.status.code(), Some(2), "{args:?}");
+    assert!(
+      stderr(&output).contains("the custom goal is for a 4x4 board"),
+      "{}",
+      stderr(&output)
+    );
+  }
+}
+
+#[test]
+fn check_says_a_position_is_solvable() {
+  let output = run("check_solvable", &["--check", "123456789abcdef0"]);
+  assert_eq!(stdout(&output), "solvable\n");
+  assert!(output.status.success(), "{}", stderr(&output));
+
+  let output = run(
+    "check_solvable",
+    &["--check", "123456789abc0def", "--with-length"],
+  );
+  assert_eq!(stdout(&output), "solvable 3\n");
+  assert!(output.status.success(), "{}", stderr(&output));
+}
+
+/// Sam Loyd's puzzle, with the 14 and 15 swapped.
+#[test]
+fn check_says_a_position_is_unsolvable() {
+  let output = run("check_unsolvable", &["--check", "123456789abcdfe0"]);
+  assert_eq!(stdout(&output), "unsolvable\n");
+  assert_eq!(stderr(&output), "");
+  assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn check_reads_the_code_from_stdin() {
+  let output = run_with_input("check_stdin", &["--check", "-"], "123456789abc0def\n");
+  assert_eq!(stdout(&output), "solvable\n");
+  assert!(output.status.success(), "{}", stderr(&output));
+}
+
+#[test]
+fn import_reads_the_moves_from_stdin() {
+  let output = run_with_input(
+    "import_stdin",
+    &["--import", "-", "--exit-after"],
+    "# A comment\nstart 123456789abc0def\nLLL\n",
+  );
+  assert_eq!(
+    stdout(&output),
+    " 1  2  3  4\n 5  6  7  8\n 9 10 11 12\n13 14 15  .\n"
+  );
+  assert!(output.status.success(), "{}", stderr(&output));
+}
+
+/// Check that every tag in the SVG is closed, in order, inside the one svg element.
+/// Returns the text of each text element.
+fn svg_texts(svg: &str) -> Vec<String> {
+  assert!(svg.starts_with("<svg "), "{svg}");
+  assert!(svg.trim_end().ends_with("</svg>"), "{svg}");
+  let mut open: Vec<&str> = Vec::new();
+  let mut texts = Vec::new();
+  let mut rest = svg;
+  while let Some(start) = rest.find('<') {
+    let end = start + rest[start..].find('>').expect("a tag isn't closed");
+    let tag = &rest[start + 1..end];
+    if let Some(name) = tag.strip_prefix('/') {
+      assert_eq!(open.pop(), Some(name), "{svg}");
+    } else if !tag.ends_with('/') {
+      let name = tag.split_whitespace().next().unwrap();
+      assert!(
+        !open.is_empty() || name == "svg",
+        "{name} is outside the svg"
+      );
+      open.push(name);
+    }
+    rest = &rest[end + 1..];
+    if open.last() == Some(&"text") {
+      texts.push(rest[..rest.find('<').unwrap()].to_string());
+    }
+  }
+  assert!(open.is_empty(), "{open:?} aren't closed");
+  texts
+}
+
+#[test]
+fn snapshot_saves_the_board_as_svg() {
+  let path = home("snapshot_files").join("board.svg");
+  let output = run(
+    "snapshot",
+    &["--demo-static", "--snapshot", path.to_str().unwrap()],
+  );
+  assert!(output.status.success(), "{}", stderr(&output));
+  let texts = svg_texts(&std::fs::read_to_string(&path).unwrap());
+  let mut tiles: Vec<u32> = texts.iter().map(|text| text.parse().unwrap()).collect();
+  tiles.sort();
+  assert_eq!(tiles, (1..=15).collect::<Vec<_>>());
+}
+
+#[test]
+fn empty_imports_are_rejected() {
+  let output = run_with_input("empty_import", &["--import", "-", "--exit-after"], "");
+  assert_eq!(stdout(&output), "");
+  assert_eq!(output.status.code(), Some(2));
+  assert!(
+    stderr(&output).contains("-: there are no moves in it"),
+    "{}",
+    stderr(&output)
+  );
+}
+
+#[test]
+fn imports_play_the_moves_before_one_that_cant_be_made() {
+  let output = run_with_input(
+    "partial_import",
+    &["--import", "-", "--exit-after"],
+    "start 123456789abc0def\nLLUL\n",
+  );
+  assert_eq!(
+    stdout(&output),
+    " 1  2  3  4\n 5  6  7  8\n 9 10 11 12\n13 14  . 15\n"
+  );
+  assert!(
+    stderr(&output).contains("move 3 in --import (up) can't be made, so only the 2 moves"),
+    "{}",
+    stderr(&output)
+  );
+  assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn imports_for_another_size_of_goal_are_rejected() {
+  let goal = home("import_size_files").join("goal");
+  std::fs::write(&goal, "87654321fedcba90\n").unwrap();
+  let output = run_with_input(
+    "import_size",
+    &["--goal-file", goal.to_str().unwrap(), "--import", "-"],
+    "start 123456780\nL\n",
+  );
+  assert_eq!(output.status.code(), Some(2));
+  assert!(
+    stderr(&output).contains("the custom goal is for a 4x4 board, not 3x3"),
+    "{}",
+    stderr(&output)
+  );
+}
+
+#[test]
+fn check_within_a_budget() {
+  let output = run(
+    "check_within",
+    &["--check", "123456789abc0def", "--within", "3"],
+  );
+  assert_eq!(stdout(&output), "solvable 3\n");
+  assert!(output.status.success(), "{}", stderr(&output));
+
+  let output = run(
+    "check_within",
+    &["--check", "123456789abc0def", "--within", "2"],
+  );
+  assert_eq!(stdout(&output), "not within 2\n");
+  assert_eq!(stderr(&output), "");
+  assert_eq!(output.status.code(), Some(1));
+}